@@ -0,0 +1,458 @@
+use std::env;
+use std::io::Read;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+use serde::Deserialize;
+use serde_json::json;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use symspell::{
+    AsciiStringStrategy, StringStrategy, SymSpell, SymSpellBuilder, SymSpellError,
+    UnicodeStringStrategy, Verbosity,
+};
+
+const USAGE: &str = "\
+Usage: symspell-server --dictionary <PATH> [OPTIONS]
+
+Serves spelling correction over HTTP/JSON.
+
+Endpoints:
+  GET  /health
+  POST /lookup             {\"input\": \"roket\", \"verbosity\": \"top\", \"max_edit_distance\": 2}
+  POST /lookup_compound    {\"input\": \"whereis th elove\", \"max_edit_distance\": 2}
+  POST /word_segmentation  {\"input\": \"thequickbrownfox\", \"max_edit_distance\": 2}
+  POST /reload             reloads the dictionaries from disk
+
+Options:
+  -d, --dictionary <PATH>         Word frequency dictionary
+  -b, --bigram-dictionary <PATH>  Bigram frequency dictionary
+  -a, --address <ADDRESS>         Address to listen on [default: 127.0.0.1:8080]
+      --threads <N>               Number of worker threads [default: 4]
+      --max-edit-distance <N>     Maximum dictionary edit distance [default: 2]
+      --prefix-length <N>         Length of word prefixes used for spell checking [default: 7]
+      --count-threshold <N>       Minimum frequency count of dictionary words [default: 1]
+      --strategy <STRATEGY>       unicode or ascii [default: unicode]
+      --separator <SEPARATOR>     Column separator of the dictionaries [default: \" \"]
+  -h, --help                      Print help
+";
+
+const MAX_BODY_SIZE: u64 = 1024 * 1024;
+
+struct Options {
+    dictionary: String,
+    bigram_dictionary: Option<String>,
+    address: String,
+    threads: usize,
+    max_edit_distance: i64,
+    prefix_length: i64,
+    count_threshold: i64,
+    ascii: bool,
+    separator: String,
+}
+
+#[derive(Deserialize)]
+struct LookupRequest {
+    input: String,
+    #[serde(default)]
+    verbosity: Option<String>,
+    #[serde(default)]
+    max_edit_distance: Option<i64>,
+}
+
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl From<SymSpellError> for ApiError {
+    fn from(error: SymSpellError) -> Self {
+        ApiError::new(400, error.to_string())
+    }
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let result = if options.ascii {
+        serve::<AsciiStringStrategy>(options)
+    } else {
+        serve::<UnicodeStringStrategy>(options)
+    };
+
+    if let Err(message) = result {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut dictionary = None;
+    let mut options = Options {
+        dictionary: String::new(),
+        bigram_dictionary: None,
+        address: "127.0.0.1:8080".to_string(),
+        threads: 4,
+        max_edit_distance: 2,
+        prefix_length: 7,
+        count_threshold: 1,
+        ascii: false,
+        separator: " ".to_string(),
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} requires a value", name));
+
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            "-d" | "--dictionary" => dictionary = Some(value(&arg)?),
+            "-b" | "--bigram-dictionary" => options.bigram_dictionary = Some(value(&arg)?),
+            "-a" | "--address" => options.address = value(&arg)?,
+            "--threads" => options.threads = parse_number(&arg, value(&arg)?)?.max(1) as usize,
+            "--max-edit-distance" => options.max_edit_distance = parse_number(&arg, value(&arg)?)?,
+            "--prefix-length" => options.prefix_length = parse_number(&arg, value(&arg)?)?,
+            "--count-threshold" => options.count_threshold = parse_number(&arg, value(&arg)?)?,
+            "--strategy" => {
+                options.ascii = match value(&arg)?.as_str() {
+                    "unicode" => false,
+                    "ascii" => true,
+                    other => return Err(format!("unknown strategy {}", other)),
+                }
+            }
+            "--separator" => options.separator = value(&arg)?,
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    options.dictionary = dictionary.ok_or("--dictionary is required")?;
+    Ok(options)
+}
+
+fn parse_number(name: &str, value: String) -> Result<i64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got {}", name, value))
+}
+
+fn load<T: StringStrategy>(options: &Options) -> Result<SymSpell<T>, String> {
+    let mut symspell: SymSpell<T> = SymSpellBuilder::default()
+        .max_dictionary_edit_distance(options.max_edit_distance)
+        .prefix_length(options.prefix_length)
+        .count_threshold(options.count_threshold)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    if !symspell.load_dictionary(&options.dictionary, 0, 1, &options.separator) {
+        return Err(format!("unable to load dictionary {}", options.dictionary));
+    }
+    if let Some(bigram_dictionary) = &options.bigram_dictionary {
+        if !symspell.load_bigram_dictionary(bigram_dictionary, 0, 2, &options.separator) {
+            return Err(format!(
+                "unable to load bigram dictionary {}",
+                bigram_dictionary
+            ));
+        }
+    }
+    Ok(symspell)
+}
+
+fn serve<T: StringStrategy + Send + Sync + 'static>(options: Options) -> Result<(), String> {
+    let symspell = Arc::new(RwLock::new(Arc::new(load::<T>(&options)?)));
+    let server = Arc::new(Server::http(&options.address).map_err(|e| e.to_string())?);
+    let options = Arc::new(options);
+
+    eprintln!("listening on http://{}", options.address);
+
+    let workers: Vec<_> = (0..options.threads)
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let symspell = Arc::clone(&symspell);
+            let options = Arc::clone(&options);
+
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    // a panicking request must not take the worker down, the
+                    // dropped request is answered with a 500 by tiny_http
+                    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                        handle(request, &symspell, &options)
+                    }));
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+fn handle<T: StringStrategy>(
+    mut request: Request,
+    symspell: &RwLock<Arc<SymSpell<T>>>,
+    options: &Options,
+) {
+    let result = route(&mut request, symspell, options);
+
+    let (status, body) = match result {
+        Ok(body) => (200, body),
+        Err(error) => (error.status, json!({ "error": error.message })),
+    };
+
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(content_type);
+    let _ = request.respond(response);
+}
+
+fn route<T: StringStrategy>(
+    request: &mut Request,
+    symspell: &RwLock<Arc<SymSpell<T>>>,
+    options: &Options,
+) -> Result<serde_json::Value, ApiError> {
+    match (request.method(), request.url()) {
+        (Method::Get, "/health") => {
+            let symspell = current(symspell);
+            Ok(json!({ "status": "ok", "words": symspell.word_count() }))
+        }
+        (Method::Post, "/reload") => load::<T>(options)
+            .map(|reloaded| {
+                let words = reloaded.word_count();
+                *symspell.write().unwrap() = Arc::new(reloaded);
+                json!({ "status": "reloaded", "words": words })
+            })
+            .map_err(|message| ApiError::new(500, message)),
+        (Method::Post, "/lookup") => read_body(request).and_then(|body| {
+            let symspell = current(symspell);
+            let max_edit_distance = validate(&symspell, body.max_edit_distance)?;
+            let verbosity = match body.verbosity.as_deref().unwrap_or("top") {
+                "top" => Verbosity::Top,
+                "closest" => Verbosity::Closest,
+                "all" => Verbosity::All,
+                other => {
+                    return Err(ApiError::new(400, format!("unknown verbosity {}", other)));
+                }
+            };
+            let suggestions = symspell.try_lookup(&body.input, verbosity, max_edit_distance)?;
+            Ok(json!({ "suggestions": suggestions }))
+        }),
+        (Method::Post, "/lookup_compound") => read_body(request).and_then(|body| {
+            let symspell = current(symspell);
+            let max_edit_distance = validate(&symspell, body.max_edit_distance)?;
+            let suggestions = symspell.try_lookup_compound(&body.input, max_edit_distance)?;
+            Ok(json!({ "suggestions": suggestions }))
+        }),
+        (Method::Post, "/word_segmentation") => read_body(request).and_then(|body| {
+            let symspell = current(symspell);
+            let max_edit_distance = validate(&symspell, body.max_edit_distance)?;
+            let composition = symspell.try_word_segmentation(&body.input, max_edit_distance)?;
+            Ok(json!({ "composition": composition }))
+        }),
+        (_, "/health" | "/reload" | "/lookup" | "/lookup_compound" | "/word_segmentation") => {
+            Err(ApiError::new(405, "method not allowed"))
+        }
+        _ => Err(ApiError::new(404, "not found")),
+    }
+}
+
+fn current<T: StringStrategy>(symspell: &RwLock<Arc<SymSpell<T>>>) -> Arc<SymSpell<T>> {
+    Arc::clone(&symspell.read().unwrap())
+}
+
+fn read_body(request: &mut Request) -> Result<LookupRequest, ApiError> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_SIZE + 1)
+        .read_to_string(&mut body)
+        .map_err(|_| ApiError::new(400, "request body is not valid UTF-8"))?;
+
+    if body.len() as u64 > MAX_BODY_SIZE {
+        return Err(ApiError::new(413, "request body is too large"));
+    }
+
+    serde_json::from_str(&body).map_err(|e| ApiError::new(400, e.to_string()))
+}
+
+fn validate<T: StringStrategy>(
+    symspell: &SymSpell<T>,
+    max_edit_distance: Option<i64>,
+) -> Result<i64, ApiError> {
+    let max_edit_distance = max_edit_distance.unwrap_or(symspell.max_dictionary_edit_distance());
+
+    if max_edit_distance < 0 {
+        return Err(ApiError::new(400, "max_edit_distance must not be negative"));
+    }
+    Ok(max_edit_distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use tiny_http::TestRequest;
+
+    use super::*;
+
+    fn dictionary(name: &str, content: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("symspell-server-{}-{}", process::id(), name));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn setup(path: &Path) -> (RwLock<Arc<SymSpell<UnicodeStringStrategy>>>, Options) {
+        let args = ["--dictionary", path.to_str().unwrap()].map(|arg| arg.to_string());
+        let options = parse_args(args.into_iter()).unwrap();
+        let symspell = RwLock::new(Arc::new(load(&options).unwrap()));
+        (symspell, options)
+    }
+
+    fn post(path: &str, body: &'static str) -> Request {
+        TestRequest::new()
+            .with_method(Method::Post)
+            .with_path(path)
+            .with_body(body)
+            .into()
+    }
+
+    fn status(result: Result<serde_json::Value, ApiError>) -> u16 {
+        result.map(|_| 200).unwrap_or_else(|error| error.status)
+    }
+
+    #[test]
+    fn validate_max_edit_distance() {
+        let symspell = SymSpell::<UnicodeStringStrategy>::default();
+        assert_eq!(2, validate(&symspell, None).ok().unwrap());
+        assert_eq!(1, validate(&symspell, Some(1)).ok().unwrap());
+        let error = validate(&symspell, Some(-1)).err().unwrap();
+        assert_eq!(400, error.status);
+        assert_eq!("max_edit_distance must not be negative", error.message);
+    }
+
+    #[test]
+    fn lookup() {
+        let path = dictionary("lookup", "rocket 10\n");
+        let (symspell, options) = setup(&path);
+
+        let result = route(
+            &mut post("/lookup", r#"{"input": "roket"}"#),
+            &symspell,
+            &options,
+        );
+        assert_eq!("rocket", result.ok().unwrap()["suggestions"][0]["term"]);
+
+        let error = route(
+            &mut post("/lookup", r#"{"input": "roket", "max_edit_distance": 3}"#),
+            &symspell,
+            &options,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(400, error.status);
+        assert_eq!(
+            SymSpellError::MaxEditDistanceTooLarge {
+                max_edit_distance: 3,
+                max_dictionary_edit_distance: 2,
+            }
+            .to_string(),
+            error.message
+        );
+
+        let error = route(
+            &mut post("/lookup", r#"{"input": "roket", "verbosity": "some"}"#),
+            &symspell,
+            &options,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(400, error.status);
+        assert_eq!("unknown verbosity some", error.message);
+
+        assert_eq!(
+            400,
+            status(route(&mut post("/lookup", "{}"), &symspell, &options))
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn request_errors() {
+        let path = dictionary("errors", "rocket 10\n");
+        let (symspell, options) = setup(&path);
+
+        let too_large: &'static str = Box::leak(
+            format!(r#"{{"input": "{}"}}"#, "a".repeat(MAX_BODY_SIZE as usize)).into_boxed_str(),
+        );
+        assert_eq!(
+            413,
+            status(route(
+                &mut post("/word_segmentation", too_large),
+                &symspell,
+                &options
+            ))
+        );
+
+        let mut get = TestRequest::new()
+            .with_method(Method::Get)
+            .with_path("/lookup")
+            .into();
+        assert_eq!(405, status(route(&mut get, &symspell, &options)));
+        assert_eq!(
+            404,
+            status(route(&mut post("/unknown", "{}"), &symspell, &options))
+        );
+        assert_eq!(
+            200,
+            status(route(
+                &mut post("/word_segmentation", r#"{"input": ""}"#),
+                &symspell,
+                &options
+            ))
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reload() {
+        let path = dictionary("reload", "rocket 10\n");
+        let (symspell, options) = setup(&path);
+        assert_eq!(1, current(&symspell).word_count());
+        let before = current(&symspell);
+
+        fs::write(&path, "rocket 10\npocket 5\n").unwrap();
+        let result = route(&mut post("/reload", ""), &symspell, &options);
+        assert_eq!(2, result.ok().unwrap()["words"]);
+        assert_eq!(2, current(&symspell).word_count());
+        // requests still holding the previous index keep using it
+        assert_eq!(1, before.word_count());
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            500,
+            status(route(&mut post("/reload", ""), &symspell, &options))
+        );
+        assert_eq!(2, current(&symspell).word_count());
+    }
+}
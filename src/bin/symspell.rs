@@ -0,0 +1,374 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process;
+
+use symspell::{
+    AsciiStringStrategy, StringStrategy, SymSpell, SymSpellBuilder, UnicodeStringStrategy,
+    Verbosity,
+};
+
+const USAGE: &str = "\
+Usage: symspell --dictionary <PATH> [OPTIONS] [FILE]...
+
+Spell checks every line of the given files (or stdin when no file is given).
+TSV rows are input, term, distance and count; an input without suggestions
+gets a row with empty fields.
+
+Options:
+  -d, --dictionary <PATH>         Word frequency dictionary
+  -b, --bigram-dictionary <PATH>  Bigram frequency dictionary
+  -m, --mode <MODE>               lookup, compound or segmentation [default: lookup]
+      --max-edit-distance <N>     Maximum edit distance [default: 2]
+      --prefix-length <N>         Length of word prefixes used for spell checking [default: 7]
+      --count-threshold <N>       Minimum frequency count of dictionary words [default: 1]
+      --verbosity <VERBOSITY>     top, closest or all, used by lookup [default: top]
+      --strategy <STRATEGY>       unicode or ascii [default: unicode]
+      --separator <SEPARATOR>     Column separator of the dictionaries [default: \" \"]
+      --format <FORMAT>           tsv or json [default: tsv]
+  -h, --help                      Print help
+";
+
+#[derive(Clone, Copy)]
+enum Mode {
+    Lookup,
+    Compound,
+    Segmentation,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Tsv,
+    Json,
+}
+
+struct Options {
+    dictionary: String,
+    bigram_dictionary: Option<String>,
+    mode: Mode,
+    max_edit_distance: i64,
+    prefix_length: i64,
+    count_threshold: i64,
+    verbosity: Verbosity,
+    ascii: bool,
+    separator: String,
+    format: Format,
+    files: Vec<String>,
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let result = if options.ascii {
+        run::<AsciiStringStrategy>(&options)
+    } else {
+        run::<UnicodeStringStrategy>(&options)
+    };
+
+    if let Err(message) = result {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut dictionary = None;
+    let mut options = Options {
+        dictionary: String::new(),
+        bigram_dictionary: None,
+        mode: Mode::Lookup,
+        max_edit_distance: 2,
+        prefix_length: 7,
+        count_threshold: 1,
+        verbosity: Verbosity::Top,
+        ascii: false,
+        separator: " ".to_string(),
+        format: Format::Tsv,
+        files: Vec::new(),
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} requires a value", name));
+
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            "-d" | "--dictionary" => dictionary = Some(value(&arg)?),
+            "-b" | "--bigram-dictionary" => options.bigram_dictionary = Some(value(&arg)?),
+            "-m" | "--mode" => {
+                options.mode = match value(&arg)?.as_str() {
+                    "lookup" => Mode::Lookup,
+                    "compound" => Mode::Compound,
+                    "segmentation" => Mode::Segmentation,
+                    other => return Err(format!("unknown mode {}", other)),
+                }
+            }
+            "--max-edit-distance" => options.max_edit_distance = parse_number(&arg, value(&arg)?)?,
+            "--prefix-length" => options.prefix_length = parse_number(&arg, value(&arg)?)?,
+            "--count-threshold" => options.count_threshold = parse_number(&arg, value(&arg)?)?,
+            "--verbosity" => {
+                options.verbosity = match value(&arg)?.as_str() {
+                    "top" => Verbosity::Top,
+                    "closest" => Verbosity::Closest,
+                    "all" => Verbosity::All,
+                    other => return Err(format!("unknown verbosity {}", other)),
+                }
+            }
+            "--strategy" => {
+                options.ascii = match value(&arg)?.as_str() {
+                    "unicode" => false,
+                    "ascii" => true,
+                    other => return Err(format!("unknown strategy {}", other)),
+                }
+            }
+            "--separator" => options.separator = value(&arg)?,
+            "--format" => {
+                options.format = match value(&arg)?.as_str() {
+                    "tsv" => Format::Tsv,
+                    "json" => Format::Json,
+                    other => return Err(format!("unknown format {}", other)),
+                }
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option {}", arg));
+            }
+            _ => options.files.push(arg),
+        }
+    }
+
+    options.dictionary = dictionary.ok_or("--dictionary is required")?;
+    Ok(options)
+}
+
+fn parse_number(name: &str, value: String) -> Result<i64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got {}", name, value))
+}
+
+fn run<T: StringStrategy>(options: &Options) -> Result<(), String> {
+    let mut symspell: SymSpell<T> = SymSpellBuilder::default()
+        .max_dictionary_edit_distance(options.max_edit_distance)
+        .prefix_length(options.prefix_length)
+        .count_threshold(options.count_threshold)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    if !symspell.load_dictionary(&options.dictionary, 0, 1, &options.separator) {
+        return Err(format!("unable to load dictionary {}", options.dictionary));
+    }
+    if let Some(bigram_dictionary) = &options.bigram_dictionary {
+        if !symspell.load_bigram_dictionary(bigram_dictionary, 0, 2, &options.separator) {
+            return Err(format!(
+                "unable to load bigram dictionary {}",
+                bigram_dictionary
+            ));
+        }
+    }
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    if options.files.is_empty() {
+        process_lines(&symspell, options, io::stdin().lock(), &mut out)?;
+    }
+    for path in &options.files {
+        if path == "-" {
+            process_lines(&symspell, options, io::stdin().lock(), &mut out)?;
+        } else {
+            let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+            process_lines(&symspell, options, BufReader::new(file), &mut out)?;
+        }
+    }
+
+    out.flush().map_err(|e| e.to_string())
+}
+
+fn process_lines<T: StringStrategy>(
+    symspell: &SymSpell<T>,
+    options: &Options,
+    input: impl BufRead,
+    out: &mut impl Write,
+) -> Result<(), String> {
+    for line in input.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+
+        let written = match options.mode {
+            Mode::Lookup => {
+                let suggestions =
+                    symspell.lookup(&line, options.verbosity, options.max_edit_distance);
+                write_suggestions(out, options.format, &line, &suggestions)
+            }
+            Mode::Compound => {
+                let suggestions = symspell.lookup_compound(&line, options.max_edit_distance);
+                write_suggestions(out, options.format, &line, &suggestions)
+            }
+            Mode::Segmentation => {
+                let composition = symspell.word_segmentation(&line, options.max_edit_distance);
+                match options.format {
+                    Format::Tsv => writeln!(
+                        out,
+                        "{}\t{}\t{}\t{}",
+                        line,
+                        composition.segmented_string,
+                        composition.distance_sum,
+                        composition.prob_log_sum
+                    ),
+                    Format::Json => writeln!(
+                        out,
+                        "{}",
+                        serde_json::json!({ "input": line, "composition": composition })
+                    ),
+                }
+            }
+        };
+        written.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn write_suggestions(
+    out: &mut impl Write,
+    format: Format,
+    input: &str,
+    suggestions: &[symspell::Suggestion],
+) -> io::Result<()> {
+    match format {
+        Format::Tsv => {
+            // keep one row per input line when there is nothing to suggest
+            if suggestions.is_empty() {
+                return writeln!(out, "{}\t\t\t", input);
+            }
+            for suggestion in suggestions {
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}",
+                    input, suggestion.term, suggestion.distance, suggestion.count
+                )?;
+            }
+            Ok(())
+        }
+        Format::Json => writeln!(
+            out,
+            "{}",
+            serde_json::json!({ "input": input, "suggestions": suggestions })
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn symspell() -> SymSpell<UnicodeStringStrategy> {
+        let mut symspell = SymSpell::default();
+        for line in ["it 100", "was 80", "the 200"] {
+            symspell.load_dictionary_line(line, 0, 1, " ");
+        }
+        symspell
+    }
+
+    fn process(options: &Options, input: &str) -> String {
+        let mut out = Vec::new();
+        process_lines(&symspell(), options, input.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn parse_args_defaults() {
+        let options = parse_args(args(&["-d", "words.txt", "a.txt", "-"])).unwrap();
+        assert_eq!("words.txt", options.dictionary);
+        assert!(matches!(options.mode, Mode::Lookup));
+        assert!(matches!(options.format, Format::Tsv));
+        assert_eq!(Verbosity::Top, options.verbosity);
+        assert_eq!(2, options.max_edit_distance);
+        assert_eq!(vec!["a.txt", "-"], options.files);
+    }
+
+    #[test]
+    fn parse_args_options() {
+        let options = parse_args(args(&[
+            "--dictionary",
+            "words.txt",
+            "--mode",
+            "segmentation",
+            "--format",
+            "json",
+            "--max-edit-distance",
+            "1",
+            "--strategy",
+            "ascii",
+        ]))
+        .unwrap();
+        assert!(matches!(options.mode, Mode::Segmentation));
+        assert!(matches!(options.format, Format::Json));
+        assert_eq!(1, options.max_edit_distance);
+        assert!(options.ascii);
+    }
+
+    #[test]
+    fn parse_args_errors() {
+        assert_eq!(
+            Some("--dictionary is required".to_string()),
+            parse_args(args(&[])).err()
+        );
+        assert_eq!(
+            Some("unknown mode fast".to_string()),
+            parse_args(args(&["-d", "words.txt", "-m", "fast"])).err()
+        );
+        assert_eq!(
+            Some("--max-edit-distance expects a number, got two".to_string()),
+            parse_args(args(&["-d", "words.txt", "--max-edit-distance", "two"])).err()
+        );
+        assert_eq!(
+            Some("--separator requires a value".to_string()),
+            parse_args(args(&["-d", "words.txt", "--separator"])).err()
+        );
+        assert_eq!(
+            Some("unknown option --fast".to_string()),
+            parse_args(args(&["-d", "words.txt", "--fast"])).err()
+        );
+    }
+
+    #[test]
+    fn process_lookup() {
+        let options = parse_args(args(&["-d", "words.txt"])).unwrap();
+        assert_eq!("wsa\twas\t1\t80\n", process(&options, "wsa\n"));
+        assert_eq!(
+            "xyzzy\t\t\t\nwsa\twas\t1\t80\n",
+            process(&options, "xyzzy\nwsa\n")
+        );
+
+        let options = parse_args(args(&["-d", "words.txt", "--format", "json"])).unwrap();
+        assert_eq!(
+            "{\"input\":\"xyzzy\",\"suggestions\":[]}\n",
+            process(&options, "xyzzy\n")
+        );
+    }
+
+    #[test]
+    fn process_segmentation_with_blank_lines() {
+        let options = parse_args(args(&["-d", "words.txt", "-m", "segmentation"])).unwrap();
+        let output = process(&options, "itwas\n\nthe\n");
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(3, lines.len());
+        assert!(lines[0].starts_with("itwas\tit was\t1\t"));
+        assert_eq!("\t\t0\t0", lines[1]);
+        assert!(lines[2].starts_with("the\tthe\t0\t"));
+    }
+}
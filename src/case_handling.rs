@@ -0,0 +1,43 @@
+use std::borrow::Cow;
+
+/// How `SymSpell` treats letter case, applied to dictionary words and to
+/// the inputs of `lookup`, `lookup_compound` and `word_segmentation` alike.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CaseHandling {
+    /// Keep case as is, but lowercase the sentences of `lookup_compound`
+    /// and `lookup_compound_beam`, as versions without `CaseHandling` did.
+    #[default]
+    LowercaseCompound,
+    /// Lowercase dictionary words and inputs, suggestions are lowercase.
+    Lowercase,
+    /// Keep case as is, "US" and "us" are different words.
+    Sensitive,
+    /// Match regardless of case, but return suggestions in the casing of
+    /// the dictionary, e.g. "iphone" is corrected to "iPhone". When a word
+    /// is loaded in several casings, the one with the highest count wins,
+    /// ties go to the casing that sorts first.
+    Insensitive,
+}
+
+impl CaseHandling {
+    pub(crate) fn apply<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        match self {
+            CaseHandling::LowercaseCompound | CaseHandling::Sensitive => Cow::Borrowed(s),
+            CaseHandling::Lowercase | CaseHandling::Insensitive => Cow::Owned(s.to_lowercase()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply() {
+        assert_eq!(CaseHandling::Lowercase.apply("iPhone"), "iphone");
+        assert_eq!(CaseHandling::Insensitive.apply("ÉCOLE"), "école");
+        assert_eq!(CaseHandling::Sensitive.apply("iPhone"), "iPhone");
+        assert_eq!(CaseHandling::LowercaseCompound.apply("iPhone"), "iPhone");
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hash function used to key the delete index.
+///
+/// The hashes are stored in the index (and serialized with it when the
+/// `serde` feature is enabled), so an index has to be queried with the same
+/// hasher it was built with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeleteHasher {
+    /// XXH64 with the given seed. Stable across platforms and Rust versions.
+    XxHash64 { seed: u64 },
+    /// FNV-1a with the seed mixed into the offset basis. Stable across
+    /// platforms and Rust versions.
+    Fnv1a { seed: u64 },
+    /// The standard library `DefaultHasher`. Its algorithm is unspecified
+    /// and may change between Rust versions, so indexes built with it should
+    /// not be persisted.
+    Std,
+}
+
+impl Default for DeleteHasher {
+    fn default() -> Self {
+        DeleteHasher::XxHash64 { seed: 0 }
+    }
+}
+
+impl DeleteHasher {
+    pub fn hash(&self, s: &str) -> u64 {
+        match *self {
+            DeleteHasher::XxHash64 { seed } => xxhash_rust::xxh64::xxh64(s.as_bytes(), seed),
+            DeleteHasher::Fnv1a { seed } => {
+                s.bytes().fold(FNV_OFFSET_BASIS ^ seed, |hash, byte| {
+                    (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+                })
+            }
+            DeleteHasher::Std => {
+                let mut hasher = DefaultHasher::new();
+                s.hash(&mut hasher);
+                hasher.finish()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xxhash64_is_stable() {
+        let hasher = DeleteHasher::XxHash64 { seed: 0 };
+        assert_eq!(hasher.hash(""), 0xef46_db37_51d8_e999);
+        assert_eq!(hasher.hash("a"), 0xd24e_c4f1_a98c_6e5b);
+    }
+
+    #[test]
+    fn fnv1a_is_stable() {
+        let hasher = DeleteHasher::Fnv1a { seed: 0 };
+        assert_eq!(hasher.hash(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hasher.hash("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn seed_changes_hash() {
+        assert_ne!(
+            DeleteHasher::XxHash64 { seed: 0 }.hash("rocket"),
+            DeleteHasher::XxHash64 { seed: 1 }.hash("rocket")
+        );
+        assert_ne!(
+            DeleteHasher::Fnv1a { seed: 0 }.hash("rocket"),
+            DeleteHasher::Fnv1a { seed: 1 }.hash("rocket")
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use strsim::damerau_levenshtein;
 
 pub fn distance(string: &str, other: &str, max_distance: i64) -> i64 {
@@ -9,3 +11,305 @@ pub fn distance(string: &str, other: &str, max_distance: i64) -> i64 {
         -1
     }
 }
+
+/// A single edit turning an input into a suggestion. Positions are indices of
+/// characters (as defined by the `StringStrategy`) in the input, and refer to
+/// these characters also after they moved in a transposition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EditOperation {
+    /// `text` is inserted before the character at `position`.
+    Insert { position: usize, text: String },
+    /// The character `text` at `position` is deleted.
+    Delete { position: usize, text: String },
+    /// The character `from` at `position` is replaced by `to`.
+    Substitute {
+        position: usize,
+        from: String,
+        to: String,
+    },
+    /// The character `first` at `position` swaps places with the following
+    /// character `second`. Characters between the two are deleted, and
+    /// characters to put between `second` and `first` are inserted before
+    /// `first`, by separate operations.
+    Transpose {
+        position: usize,
+        first: String,
+        second: String,
+    },
+}
+
+#[derive(Clone, Copy)]
+enum Step {
+    Start,
+    Match,
+    Substitute,
+    Insert,
+    Delete,
+    Transpose(usize, usize),
+}
+
+/// Edit script with the least operations turning `a` into `b`, using the
+/// unrestricted Damerau-Levenshtein distance `distance` is based on, so its
+/// length equals the distance.
+pub fn operations(a: &[&str], b: &[&str]) -> Vec<EditOperation> {
+    let (n, m) = (a.len(), b.len());
+    let max = n + m;
+
+    // distances are offset by one row and column holding `max`, as in the
+    // Lowrance-Wagner algorithm
+    let mut d = vec![vec![max; m + 2]; n + 2];
+    let mut steps = vec![vec![Step::Start; m + 2]; n + 2];
+    for i in 0..=n {
+        d[i + 1][1] = i;
+        steps[i + 1][1] = Step::Delete;
+    }
+    for j in 0..=m {
+        d[1][j + 1] = j;
+        steps[1][j + 1] = Step::Insert;
+    }
+
+    let mut last_row: HashMap<&str, usize> = HashMap::new();
+    for i in 1..=n {
+        let mut last_match_col = 0;
+        for j in 1..=m {
+            let k = last_row.get(b[j - 1]).copied().unwrap_or(0);
+            let l = last_match_col;
+
+            let (mut best, mut step) = if a[i - 1] == b[j - 1] {
+                last_match_col = j;
+                (d[i][j], Step::Match)
+            } else {
+                (d[i][j] + 1, Step::Substitute)
+            };
+            if d[i][j + 1] + 1 < best {
+                best = d[i][j + 1] + 1;
+                step = Step::Delete;
+            }
+            if d[i + 1][j] + 1 < best {
+                best = d[i + 1][j] + 1;
+                step = Step::Insert;
+            }
+            let transposition = d[k][l] + (i - k - 1) + 1 + (j - l - 1);
+            if transposition < best {
+                best = transposition;
+                step = Step::Transpose(k, l);
+            }
+
+            d[i + 1][j + 1] = best;
+            steps[i + 1][j + 1] = step;
+        }
+        last_row.insert(a[i - 1], i);
+    }
+
+    let mut operations = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        match steps[i + 1][j + 1] {
+            Step::Match => {
+                i -= 1;
+                j -= 1;
+            }
+            Step::Substitute => {
+                operations.push(EditOperation::Substitute {
+                    position: i - 1,
+                    from: a[i - 1].to_string(),
+                    to: b[j - 1].to_string(),
+                });
+                i -= 1;
+                j -= 1;
+            }
+            Step::Insert => {
+                operations.push(EditOperation::Insert {
+                    position: i,
+                    text: b[j - 1].to_string(),
+                });
+                j -= 1;
+            }
+            Step::Delete => {
+                operations.push(EditOperation::Delete {
+                    position: i - 1,
+                    text: a[i - 1].to_string(),
+                });
+                i -= 1;
+            }
+            Step::Transpose(k, l) => {
+                // pushed in reverse, the script is reversed at the end
+                for inserted in (l..j - 1).rev() {
+                    operations.push(EditOperation::Insert {
+                        position: k - 1,
+                        text: b[inserted].to_string(),
+                    });
+                }
+                for deleted in (k..i - 1).rev() {
+                    operations.push(EditOperation::Delete {
+                        position: deleted,
+                        text: a[deleted].to_string(),
+                    });
+                }
+                operations.push(EditOperation::Transpose {
+                    position: k - 1,
+                    first: a[k - 1].to_string(),
+                    second: a[i - 1].to_string(),
+                });
+                i = k - 1;
+                j = l - 1;
+            }
+            Step::Start => unreachable!("backtrace reached the border"),
+        }
+    }
+
+    operations.reverse();
+    operations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<&str> {
+        s.char_indices()
+            .map(|(i, c)| &s[i..i + c.len_utf8()])
+            .collect()
+    }
+
+    fn ops(a: &str, b: &str) -> Vec<EditOperation> {
+        operations(&chars(a), &chars(b))
+    }
+
+    #[test]
+    fn single_operations() {
+        assert!(ops("rocket", "rocket").is_empty());
+        assert_eq!(
+            ops("roket", "rocket"),
+            vec![EditOperation::Insert {
+                position: 2,
+                text: "c".to_string()
+            }]
+        );
+        assert_eq!(
+            ops("rocket", "roket"),
+            vec![EditOperation::Delete {
+                position: 2,
+                text: "c".to_string()
+            }]
+        );
+        assert_eq!(
+            ops("rocker", "rocket"),
+            vec![EditOperation::Substitute {
+                position: 5,
+                from: "r".to_string(),
+                to: "t".to_string()
+            }]
+        );
+        assert_eq!(
+            ops("rokcet", "rocket"),
+            vec![EditOperation::Transpose {
+                position: 2,
+                first: "k".to_string(),
+                second: "c".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn transposition_with_deletion() {
+        // unrestricted Damerau-Levenshtein: "ca" -> "ac" -> "abc" is 2
+        assert_eq!(
+            ops("ca", "abc"),
+            vec![
+                EditOperation::Transpose {
+                    position: 0,
+                    first: "c".to_string(),
+                    second: "a".to_string()
+                },
+                // before "c", which the transposition moved behind "a"
+                EditOperation::Insert {
+                    position: 0,
+                    text: "b".to_string()
+                },
+            ]
+        );
+        assert_eq!(ops("cxa", "ac").len(), 2);
+    }
+
+    /// Applies an edit script to `a`, as documented on `EditOperation`.
+    fn apply(a: &str, operations: &[EditOperation]) -> String {
+        let a = chars(a);
+        // the character ending up in every slot of the input, and the slot
+        // every input character ends up in
+        let mut slots: Vec<Option<String>> = a.iter().map(|c| Some(c.to_string())).collect();
+        let mut slot_of: Vec<usize> = (0..=a.len()).collect();
+        let mut inserted = vec![Vec::new(); a.len() + 1];
+
+        for operation in operations {
+            match operation {
+                EditOperation::Substitute { position, to, .. } => {
+                    slots[*position] = Some(to.clone())
+                }
+                EditOperation::Delete { position, .. } => slots[*position] = None,
+                EditOperation::Transpose {
+                    position, second, ..
+                } => {
+                    let other = (position + 1..a.len()).find(|&p| a[p] == second).unwrap();
+                    slots.swap(*position, other);
+                    slot_of.swap(*position, other);
+                }
+                EditOperation::Insert { .. } => (),
+            }
+        }
+        for operation in operations {
+            if let EditOperation::Insert { position, text } = operation {
+                inserted[slot_of[*position]].push(text.as_str());
+            }
+        }
+
+        let mut result = String::new();
+        for (slot, texts) in inserted.iter().enumerate() {
+            result.extend(texts.iter().copied());
+            if let Some(Some(c)) = slots.get(slot) {
+                result.push_str(c);
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn transpositions_with_inserts_and_deletes() {
+        assert_eq!(apply("ca", &ops("ca", "abc")), "abc");
+        assert_eq!(apply("cxa", &ops("cxa", "ac")), "ac");
+        assert_eq!(apply("cxa", &ops("cxa", "abyc")), "abyc");
+    }
+
+    #[test]
+    fn scripts_turn_input_into_target() {
+        let words = [
+            "", "a", "ab", "ba", "abc", "ca", "cxa", "abyc", "abcdef", "badcfe", "kitten",
+            "sitting", "héllo", "hello", "whereis", "whereas", "acb", "bca",
+        ];
+        for a in words {
+            for b in words {
+                assert_eq!(apply(a, &ops(a, b)), b, "{} -> {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn length_equals_distance() {
+        let words = [
+            "", "a", "ab", "ba", "abc", "ca", "abcdef", "badcfe", "kitten", "sitting", "héllo",
+            "hello", "whereis", "whereas", "acb", "bca",
+        ];
+        for a in words {
+            for b in words {
+                assert_eq!(
+                    ops(a, b).len() as i64,
+                    distance(a, b, i64::MAX),
+                    "{} -> {}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+}
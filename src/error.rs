@@ -0,0 +1,28 @@
+use std::error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymSpellError {
+    /// The requested edit distance is bigger than the one the dictionary was built for.
+    MaxEditDistanceTooLarge {
+        max_edit_distance: i64,
+        max_dictionary_edit_distance: i64,
+    },
+}
+
+impl fmt::Display for SymSpellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymSpellError::MaxEditDistanceTooLarge {
+                max_edit_distance,
+                max_dictionary_edit_distance,
+            } => write!(
+                f,
+                "max_edit_distance ({}) is bigger than max_dictionary_edit_distance ({})",
+                max_edit_distance, max_dictionary_edit_distance
+            ),
+        }
+    }
+}
+
+impl error::Error for SymSpellError {}
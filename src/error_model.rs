@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use crate::edit_distance::EditOperation;
+use crate::string_strategy::{StringStrategy, UnicodeStringStrategy};
+
+/// Character level noisy channel model of typing errors.
+///
+/// Learns confusion counts for substitutions, insertions, deletions and
+/// transpositions from (typo, correction) pairs and estimates `P(typo | word)`
+/// as the product of the add-one smoothed probabilities of the edit
+/// operations turning `word` into `typo`. An untrained model gives every
+/// operation the probability 1.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorModel {
+    /// Intended character mapped to the characters typed instead.
+    substitutions: HashMap<String, HashMap<String, u64>>,
+    /// Characters typed although not intended.
+    insertions: HashMap<String, u64>,
+    /// Intended characters not typed.
+    deletions: HashMap<String, u64>,
+    /// Intended character mapped to the following characters it was swapped with.
+    transpositions: HashMap<String, HashMap<String, u64>>,
+    /// Occurrences of every character in the corrections.
+    characters: HashMap<String, u64>,
+    total_characters: u64,
+}
+
+impl ErrorModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Learns from a typo and its correction, split into characters like
+    /// `UnicodeStringStrategy` does.
+    pub fn train(&mut self, typo: &str, correction: &str) {
+        self.train_with(&UnicodeStringStrategy::new(), typo, correction);
+    }
+
+    /// Learns from a typo and its correction, prepared and split into
+    /// characters by `strategy`.
+    pub fn train_with<T: StringStrategy>(&mut self, strategy: &T, typo: &str, correction: &str) {
+        let (typo, correction) = (strategy.prepare(typo), strategy.prepare(correction));
+        let mut offsets = Vec::new();
+        for character in strategy.decode(&correction, &mut offsets).chars() {
+            *self.characters.entry(character.to_string()).or_default() += 1;
+            self.total_characters += 1;
+        }
+
+        for operation in strategy.edit_operations(&correction, &typo) {
+            match operation {
+                EditOperation::Substitute { from, to, .. } => {
+                    *self
+                        .substitutions
+                        .entry(from)
+                        .or_default()
+                        .entry(to)
+                        .or_default() += 1;
+                }
+                EditOperation::Insert { text, .. } => {
+                    *self.insertions.entry(text).or_default() += 1
+                }
+                EditOperation::Delete { text, .. } => *self.deletions.entry(text).or_default() += 1,
+                EditOperation::Transpose { first, second, .. } => {
+                    *self
+                        .transpositions
+                        .entry(first)
+                        .or_default()
+                        .entry(second)
+                        .or_default() += 1;
+                }
+            }
+        }
+    }
+
+    /// `P(typo | word)`, with characters as defined by `UnicodeStringStrategy`.
+    pub fn probability(&self, typo: &str, word: &str) -> f64 {
+        self.probability_with(&UnicodeStringStrategy::new(), typo, word)
+    }
+
+    /// `P(typo | word)`, with both prepared and split into characters by `strategy`.
+    pub fn probability_with<T: StringStrategy>(&self, strategy: &T, typo: &str, word: &str) -> f64 {
+        strategy
+            .edit_operations(&strategy.prepare(word), &strategy.prepare(typo))
+            .iter()
+            .map(|operation| self.operation_probability(operation))
+            .product()
+    }
+
+    fn operation_probability(&self, operation: &EditOperation) -> f64 {
+        let alphabet = self.characters.len() as f64 + 1.0;
+        let smoothed =
+            |count: u64, total: u64| ((count as f64 + 1.0) / (total as f64 + alphabet)).min(1.0);
+        let nested = |counts: &HashMap<String, HashMap<String, u64>>, a: &str, b: &str| {
+            counts
+                .get(a)
+                .and_then(|counts| counts.get(b))
+                .copied()
+                .unwrap_or(0)
+        };
+        let character = |c: &str| self.characters.get(c).copied().unwrap_or(0);
+
+        match operation {
+            EditOperation::Substitute { from, to, .. } => {
+                smoothed(nested(&self.substitutions, from, to), character(from))
+            }
+            EditOperation::Insert { text, .. } => smoothed(
+                self.insertions.get(text).copied().unwrap_or(0),
+                self.total_characters,
+            ),
+            EditOperation::Delete { text, .. } => smoothed(
+                self.deletions.get(text).copied().unwrap_or(0),
+                character(text),
+            ),
+            EditOperation::Transpose { first, second, .. } => smoothed(
+                nested(&self.transpositions, first, second),
+                character(first),
+            ),
+        }
+    }
+}
+
+/// How `lookup` ranks its candidates.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Ranking {
+    /// Smaller edit distance first, then higher count.
+    #[default]
+    Distance,
+    /// Higher `P(input | word) * P(word)` first, with `P(input | word)` from
+    /// the error model and `P(word)` from the dictionary counts. Candidates
+    /// are not limited to the smallest edit distance found.
+    NoisyChannel(Box<ErrorModel>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string_strategy::AsciiStringStrategy;
+
+    #[test]
+    fn untrained_model() {
+        let model = ErrorModel::new();
+        assert_eq!(model.probability("cet", "cat"), 1.0);
+        assert_eq!(model.probability("cat", "cat"), 1.0);
+    }
+
+    #[test]
+    fn trained_probabilities() {
+        let mut model = ErrorModel::new();
+        for _ in 0..5 {
+            model.train("bet", "but");
+            model.train("hem", "hum");
+        }
+
+        // characters b, u, t, h, m plus one for unseen ones
+        assert!((model.probability("cet", "cut") - 11.0 / 16.0).abs() < 1e-9);
+        assert!((model.probability("cet", "cat") - 1.0 / 6.0).abs() < 1e-9);
+        assert!((model.probability("ct", "cut") - 1.0 / 16.0).abs() < 1e-9);
+        assert!(model.probability("cte", "cut") < model.probability("cet", "cut"));
+        assert_eq!(model.probability("cut", "cut"), 1.0);
+    }
+
+    #[test]
+    fn non_ascii_pairs() {
+        let strategy = AsciiStringStrategy::new();
+        let mut model = ErrorModel::new();
+        model.train_with(&strategy, "hèllo", "hallo");
+
+        assert!(
+            model.probability_with(&strategy, "hêllo", "hallo")
+                > model.probability_with(&strategy, "hillo", "hallo")
+        );
+        assert_eq!(model.probability_with(&strategy, "héllo", "hello"), 1.0);
+    }
+
+    #[test]
+    fn transpositions() {
+        let mut model = ErrorModel::new();
+        model.train("teh", "the");
+
+        assert!(model.probability("teh", "the") > model.probability("tha", "the"));
+    }
+}
@@ -20,19 +20,35 @@ println!("{:?}", compound_suggestions);
 ```
 */
 
+mod case_handling;
 mod composition;
+mod delete_hasher;
 mod edit_distance;
+mod error;
+mod error_model;
+mod memory_usage;
 mod string_strategy;
 mod suggestion;
 mod symspell;
+mod tokenizer;
 #[cfg(target_arch = "wasm32")]
 mod wasm;
 
+pub use case_handling::CaseHandling;
+pub use delete_hasher::DeleteHasher;
+pub use edit_distance::EditOperation;
+pub use error::SymSpellError;
+pub use error_model::{ErrorModel, Ranking};
+pub use memory_usage::MemoryUsage;
 #[cfg(not(target_arch = "wasm32"))]
 pub use string_strategy::AsciiStringStrategy;
-pub use string_strategy::{StringStrategy, UnicodeStringStrategy, UnicodeiStringStrategy};
-pub use suggestion::Suggestion;
+pub use string_strategy::{
+    Decoded, GraphemeStringStrategy, NormalizationForm, NormalizingStringStrategy, StringStrategy,
+    UnicodeStringStrategy, UnicodeiStringStrategy,
+};
+pub use suggestion::{ContextSuggestion, SentenceSuggestion, Suggestion};
 pub use symspell::{SymSpell, SymSpellBuilder, Verbosity};
+pub use tokenizer::{Tokenizer, WhitespaceTokenizer};
 
 #[cfg(target_arch = "wasm32")]
 pub use wasm::JSSymSpell;
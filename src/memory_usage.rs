@@ -0,0 +1,19 @@
+/// Approximate heap memory used by the dictionary structures of a `SymSpell` instance, in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryUsage {
+    /// Word index, frequency counts and display forms.
+    pub words: usize,
+    /// Interned dictionary words referenced by the delete index.
+    pub terms: usize,
+    /// Delete index with its buckets of word ids.
+    pub deletes: usize,
+    /// Bigram and higher order n-gram to frequency count maps.
+    pub bigrams: usize,
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.words + self.terms + self.deletes + self.bigrams
+    }
+}
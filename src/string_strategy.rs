@@ -1,56 +1,181 @@
+use caseless::default_case_fold_str;
+use strsim::generic_damerau_levenshtein;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
 #[cfg(not(target_arch = "wasm32"))]
 use unidecode::unidecode;
 
+use crate::edit_distance::{self, EditOperation};
+
+/// String manipulation used by `SymSpell`, e.g. preprocessing and splitting
+/// strings into characters.
+///
+/// What a character is, is defined by `char_offsets` alone. `SymSpell`
+/// decodes every string into a [`Decoded`] and works on its characters,
+/// it never calls `len`, `remove`, `slice`, `suffix` or `at`. These are
+/// conveniences derived from `char_offsets`, overriding them doesn't change
+/// how `SymSpell` splits strings.
+///
+/// A strategy can carry configuration, pass a configured instance to
+/// `SymSpellBuilder::string_strategy`. `Default` provides the instance used
+/// when none is given.
 pub trait StringStrategy: Clone + Default {
-    fn new() -> Self;
-    fn prepare(&self, s: &str) -> String;
-    fn len(&self, s: &str) -> usize;
-    fn remove(&self, s: &str, index: usize) -> String;
-    fn slice(&self, s: &str, start: usize, end: usize) -> String;
-    fn suffix(&self, s: &str, start: usize) -> String;
-    fn at(&self, s: &str, i: isize) -> Option<char>;
-}
+    fn new() -> Self {
+        Self::default()
+    }
 
-#[cfg(not(target_arch = "wasm32"))]
-#[derive(Clone)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Default)]
-pub struct AsciiStringStrategy {}
+    fn prepare(&self, s: &str) -> String;
 
-#[cfg(not(target_arch = "wasm32"))]
-impl StringStrategy for AsciiStringStrategy {
-    fn new() -> Self {
-        Self {}
+    /// Appends the byte offset at which each character of `s` starts to `offsets`.
+    fn char_offsets(&self, s: &str, offsets: &mut Vec<usize>) {
+        offsets.extend(s.char_indices().map(|(i, _)| i));
     }
 
-    fn prepare(&self, s: &str) -> String {
-        unidecode(s)
+    /// Splits `s` into characters, using `offsets` as storage so that it can
+    /// be reused across calls.
+    fn decode<'a>(&self, s: &'a str, offsets: &'a mut Vec<usize>) -> Decoded<'a> {
+        offsets.clear();
+        self.char_offsets(s, offsets);
+        offsets.push(s.len());
+        Decoded { s, offsets }
     }
 
     fn len(&self, s: &str) -> usize {
-        s.len()
+        self.decode(s, &mut Vec::new()).len()
     }
 
     fn remove(&self, s: &str, index: usize) -> String {
-        let mut x = s.to_string();
-        x.remove(index);
-        x
+        let mut removed = String::new();
+        self.decode(s, &mut Vec::new())
+            .remove_into(index, &mut removed);
+        removed
     }
 
     fn slice(&self, s: &str, start: usize, end: usize) -> String {
-        s[start..end].to_string()
+        self.decode(s, &mut Vec::new())
+            .slice(start, end)
+            .to_string()
     }
 
     fn suffix(&self, s: &str, start: usize) -> String {
-        self.slice(s, start, s.len())
+        self.decode(s, &mut Vec::new()).suffix(start).to_string()
     }
 
+    /// Returns the first `char` of the character at index `i`.
     fn at(&self, s: &str, i: isize) -> Option<char> {
-        if i < 0 || i >= s.len() as isize {
+        if i < 0 {
             return None;
         }
 
-        Some(s.as_bytes()[i as usize] as char)
+        self.decode(s, &mut Vec::new())
+            .at(i as usize)
+            .and_then(|c| c.chars().next())
+    }
+
+    /// Edit distance between two strings, measured in the same units as `len`,
+    /// or -1 when it exceeds `max_distance`.
+    fn distance(&self, s: &str, other: &str, max_distance: i64) -> i64 {
+        edit_distance::distance(s, other, max_distance)
+    }
+
+    /// Edit operations turning `s` into `other`, e.g. an input into the term of
+    /// a suggestion, with positions counted in characters of `s`.
+    fn edit_operations(&self, s: &str, other: &str) -> Vec<EditOperation> {
+        let (mut s_offsets, mut other_offsets) = (Vec::new(), Vec::new());
+        let s = self.decode(s, &mut s_offsets);
+        let other = self.decode(other, &mut other_offsets);
+
+        edit_distance::operations(&s.chars(), &other.chars())
+    }
+}
+
+/// A string split into the characters of a `StringStrategy`, with O(1)
+/// access to every character and borrowed slices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decoded<'a> {
+    s: &'a str,
+    // start of every character, followed by `s.len()`
+    offsets: &'a [usize],
+}
+
+impl<'a> Decoded<'a> {
+    pub fn as_str(&self) -> &'a str {
+        self.s
+    }
+
+    /// Number of characters.
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// All characters.
+    pub fn chars(&self) -> Vec<&'a str> {
+        self.offsets
+            .windows(2)
+            .map(|w| &self.s[w[0]..w[1]])
+            .collect()
+    }
+
+    /// The character at index `i`.
+    pub fn at(&self, i: usize) -> Option<&'a str> {
+        if i < self.len() {
+            Some(&self.s[self.offsets[i]..self.offsets[i + 1]])
+        } else {
+            None
+        }
+    }
+
+    /// Characters from `start` up to, but not including, `end`.
+    pub fn slice(&self, start: usize, end: usize) -> &'a str {
+        &self.s[self.offsets[start]..self.offsets[end]]
+    }
+
+    /// Characters from `start` to the end.
+    pub fn suffix(&self, start: usize) -> &'a str {
+        &self.s[self.offsets[start]..]
+    }
+
+    /// Writes the string without the character at `index` into `out`.
+    pub fn remove_into(&self, index: usize, out: &mut String) {
+        out.clear();
+        out.push_str(&self.s[..self.offsets[index]]);
+        out.push_str(&self.s[self.offsets[index + 1]..]);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Default)]
+pub struct AsciiStringStrategy {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl StringStrategy for AsciiStringStrategy {
+    fn new() -> Self {
+        Self {}
+    }
+
+    fn prepare(&self, s: &str) -> String {
+        unidecode(s)
+    }
+
+    fn char_offsets(&self, s: &str, offsets: &mut Vec<usize>) {
+        offsets.extend(0..s.len());
+    }
+
+    /// Edit operations between the prepared (transliterated) strings, with
+    /// positions counted in characters of the prepared `s`.
+    fn edit_operations(&self, s: &str, other: &str) -> Vec<EditOperation> {
+        let (s, other) = (self.prepare(s), self.prepare(other));
+        let (mut s_offsets, mut other_offsets) = (Vec::new(), Vec::new());
+        let s = self.decode(&s, &mut s_offsets);
+        let other = self.decode(&other, &mut other_offsets);
+
+        edit_distance::operations(&s.chars(), &other.chars())
     }
 }
 
@@ -70,33 +195,92 @@ impl StringStrategy for UnicodeStringStrategy {
     fn prepare(&self, s: &str) -> String {
         s.to_string()
     }
+}
 
-    fn len(&self, s: &str) -> usize {
-        s.chars().count()
-    }
+/// Unicode normalization form applied by `NormalizingStringStrategy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NormalizationForm {
+    /// Canonical composition, e.g. "e\u{301}" becomes "é".
+    #[default]
+    Nfc,
+    /// Compatibility composition, additionally folds variants such as "ﬁ" into "fi".
+    Nfkc,
+}
 
-    fn remove(&self, s: &str, index: usize) -> String {
-        s.chars()
-            .enumerate()
-            .filter(|(ii, _)| ii != &index)
-            .map(|(_, ch)| ch)
-            .collect()
+/// Normalizes strings to a Unicode normalization form and optionally applies
+/// full Unicode case folding, so composed and decomposed forms (and, with
+/// case folding, different cases) are treated as equal while accents are kept.
+/// Otherwise handles strings like `UnicodeStringStrategy`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NormalizingStringStrategy {
+    form: NormalizationForm,
+    case_fold: bool,
+}
+
+impl Default for NormalizingStringStrategy {
+    fn default() -> Self {
+        Self::with_options(NormalizationForm::Nfc, true)
     }
+}
 
-    fn slice(&self, s: &str, start: usize, end: usize) -> String {
-        s.chars().skip(start).take(end - start).collect()
+impl NormalizingStringStrategy {
+    pub fn with_options(form: NormalizationForm, case_fold: bool) -> Self {
+        Self { form, case_fold }
     }
+}
 
-    fn suffix(&self, s: &str, start: usize) -> String {
-        s.chars().skip(start).collect::<String>()
+impl StringStrategy for NormalizingStringStrategy {
+    fn new() -> Self {
+        Self::default()
     }
 
-    fn at(&self, s: &str, i: isize) -> Option<char> {
-        if i < 0 {
-            return None;
+    fn prepare(&self, s: &str) -> String {
+        match (self.form, self.case_fold) {
+            (NormalizationForm::Nfc, false) => s.nfc().collect(),
+            (NormalizationForm::Nfkc, false) => s.nfkc().collect(),
+            (NormalizationForm::Nfc, true) => default_case_fold_str(&s.nfd().collect::<String>())
+                .nfc()
+                .collect(),
+            (NormalizationForm::Nfkc, true) => default_case_fold_str(&s.nfkd().collect::<String>())
+                .nfkc()
+                .collect(),
         }
+    }
+}
 
-        s.chars().nth(i as usize)
+/// Handles strings as sequences of extended grapheme clusters, so that a
+/// base letter with combining marks or an emoji ZWJ sequence counts as a
+/// single character.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Default)]
+pub struct GraphemeStringStrategy {}
+
+impl StringStrategy for GraphemeStringStrategy {
+    fn new() -> Self {
+        Self {}
+    }
+
+    fn prepare(&self, s: &str) -> String {
+        s.to_string()
+    }
+
+    fn char_offsets(&self, s: &str, offsets: &mut Vec<usize>) {
+        offsets.extend(s.grapheme_indices(true).map(|(i, _)| i));
+    }
+
+    fn distance(&self, s: &str, other: &str, max_distance: i64) -> i64 {
+        let s: Vec<&str> = s.graphemes(true).collect();
+        let other: Vec<&str> = other.graphemes(true).collect();
+        let distance = generic_damerau_levenshtein(&s, &other);
+
+        if distance <= max_distance as usize {
+            distance as i64
+        } else {
+            -1
+        }
     }
 }
 
@@ -171,9 +355,144 @@ mod tests {
         assert_eq!(UnicodeStringStrategy::new().at("日本語", -1), None);
     }
 
+    #[test]
+    fn normalizing_prepare_composes() {
+        let strategy = NormalizingStringStrategy::with_options(NormalizationForm::Nfc, false);
+        assert_eq!(strategy.prepare("cafe\u{301}"), "caf\u{e9}");
+        assert_eq!(strategy.prepare("Caf\u{e9}"), "Caf\u{e9}");
+        assert_eq!(strategy.prepare("\u{fb01}n"), "\u{fb01}n");
+    }
+
+    #[test]
+    fn normalizing_prepare_case_folds() {
+        let strategy = NormalizingStringStrategy::new();
+        assert_eq!(strategy.prepare("CAFE\u{301}"), "caf\u{e9}");
+        assert_eq!(strategy.prepare("Stra\u{df}e"), "strasse");
+        assert_eq!(strategy.prepare("STRASSE"), "strasse");
+        assert_eq!(strategy.prepare("\u{c9}t\u{e9}"), "\u{e9}t\u{e9}");
+    }
+
+    #[test]
+    fn normalizing_prepare_compatibility() {
+        let strategy = NormalizingStringStrategy::with_options(NormalizationForm::Nfkc, true);
+        assert_eq!(strategy.prepare("\u{fb01}N"), "fin");
+        assert_eq!(strategy.prepare("E\u{301}"), "\u{e9}");
+    }
+
+    const E_ACUTE: &str = "e\u{301}";
+    const FAMILY: &str = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+
+    #[test]
+    fn grapheme_len() {
+        let s = format!("caf{}{}", E_ACUTE, FAMILY);
+        assert_eq!(GraphemeStringStrategy::new().len(&s), 5);
+        assert_eq!(UnicodeStringStrategy::new().len(&s), 10);
+    }
+
+    #[test]
+    fn grapheme_remove() {
+        let s = format!("caf{}!", E_ACUTE);
+        assert_eq!(GraphemeStringStrategy::new().remove(&s, 3), "caf!");
+        assert_eq!(
+            GraphemeStringStrategy::new().remove(&format!("a{}b", FAMILY), 1),
+            "ab"
+        );
+    }
+
+    #[test]
+    fn grapheme_slice_and_suffix() {
+        let s = format!("{}t{}{}", E_ACUTE, E_ACUTE, FAMILY);
+        assert_eq!(
+            GraphemeStringStrategy::new().slice(&s, 0, 3),
+            format!("{}t{}", E_ACUTE, E_ACUTE)
+        );
+        assert_eq!(
+            GraphemeStringStrategy::new().suffix(&s, 2),
+            format!("{}{}", E_ACUTE, FAMILY)
+        );
+    }
+
+    #[test]
+    fn grapheme_at() {
+        let s = format!("{}t", E_ACUTE);
+        assert_eq!(GraphemeStringStrategy::new().at(&s, 0), Some('e'));
+        assert_eq!(GraphemeStringStrategy::new().at(&s, 1), Some('t'));
+        assert_eq!(GraphemeStringStrategy::new().at(&s, 2), None);
+        assert_eq!(GraphemeStringStrategy::new().at(&s, -1), None);
+    }
+
+    #[test]
+    fn grapheme_distance() {
+        let strategy = GraphemeStringStrategy::new();
+        assert_eq!(strategy.distance(&format!("caf{}", E_ACUTE), "cafe", 2), 1);
+        assert_eq!(strategy.distance(FAMILY, "\u{1F468}", 2), 1);
+        assert_eq!(
+            UnicodeStringStrategy::new().distance(FAMILY, "\u{1F468}", 2),
+            -1
+        );
+    }
+
     #[test]
     fn unicode_suffix_multibyte() {
         assert_eq!(UnicodeStringStrategy::new().suffix("héllo", 2), "llo");
         assert_eq!(UnicodeStringStrategy::new().suffix("日本語", 1), "本語");
     }
+
+    #[test]
+    fn decoded_borrows() {
+        let mut offsets = Vec::new();
+        let decoded = UnicodeStringStrategy::new().decode("héllo", &mut offsets);
+        assert_eq!(decoded.len(), 5);
+        assert_eq!(decoded.at(1), Some("é"));
+        assert_eq!(decoded.at(5), None);
+        assert_eq!(decoded.slice(1, 3), "él");
+        assert_eq!(decoded.suffix(2), "llo");
+
+        let mut removed = String::from("stale");
+        decoded.remove_into(1, &mut removed);
+        assert_eq!(removed, "hllo");
+    }
+
+    #[test]
+    fn decoded_reuses_offsets() {
+        let strategy = GraphemeStringStrategy::new();
+        let mut offsets = Vec::new();
+        assert_eq!(strategy.decode("ne\u{301}e", &mut offsets).len(), 3);
+        let decoded = strategy.decode("", &mut offsets);
+        assert!(decoded.is_empty());
+        assert_eq!(decoded.at(0), None);
+    }
+
+    #[test]
+    fn ascii_edit_operations_non_ascii() {
+        let strategy = AsciiStringStrategy::new();
+        assert!(strategy.edit_operations("héllo", "hello").is_empty());
+        assert_eq!(
+            strategy.edit_operations("hällo", "hullo"),
+            vec![EditOperation::Substitute {
+                position: 1,
+                from: "a".to_string(),
+                to: "u".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn grapheme_edit_operations() {
+        let operations = GraphemeStringStrategy::new().edit_operations("ne\u{301}", "e\u{301}n");
+        assert_eq!(
+            operations,
+            vec![EditOperation::Transpose {
+                position: 0,
+                first: "n".to_string(),
+                second: "e\u{301}".to_string()
+            }]
+        );
+        assert_eq!(
+            UnicodeStringStrategy::new()
+                .edit_operations("ne\u{301}", "e\u{301}n")
+                .len(),
+            2
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -6,6 +7,13 @@ pub struct Suggestion {
     pub term: String,
     pub distance: i64,
     pub count: i64,
+    /// Probability that this is the intended word, set by `lookup` when
+    /// `compute_confidence` is enabled.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub confidence: Option<f64>,
 }
 
 impl Suggestion {
@@ -14,6 +22,7 @@ impl Suggestion {
             term: "".to_string(),
             distance: 0,
             count: 0,
+            confidence: None,
         }
     }
 
@@ -22,17 +31,20 @@ impl Suggestion {
             term: term.into(),
             distance,
             count,
+            confidence: None,
         }
     }
 }
 
+/// Suggestions are ordered by ascending distance, then descending count,
+/// then term, so the best suggestion comes first. Equality and hashing use
+/// the same fields and ignore `confidence`.
 impl Ord for Suggestion {
     fn cmp(&self, other: &Suggestion) -> Ordering {
-        let distance_cmp = self.distance.cmp(&other.distance);
-        if distance_cmp == Ordering::Equal {
-            return self.count.cmp(&other.count);
-        }
-        distance_cmp
+        self.distance
+            .cmp(&other.distance)
+            .then_with(|| other.count.cmp(&self.count))
+            .then_with(|| self.term.cmp(&other.term))
     }
 }
 
@@ -44,7 +56,78 @@ impl PartialOrd for Suggestion {
 
 impl PartialEq for Suggestion {
     fn eq(&self, other: &Suggestion) -> bool {
-        self.distance == other.distance && self.count == other.count
+        self.cmp(other) == Ordering::Equal
     }
 }
 impl Eq for Suggestion {}
+
+impl Hash for Suggestion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.term.hash(state);
+        self.distance.hash(state);
+        self.count.hash(state);
+    }
+}
+
+/// A `Suggestion` ranked by how well it fits between its neighbouring words.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContextSuggestion {
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub suggestion: Suggestion,
+    /// Log10 probability of the suggestion in its context, higher is better.
+    pub score: f64,
+}
+
+/// A corrected sentence ranked by a language model.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SentenceSuggestion {
+    pub term: String,
+    /// Edit distance between the input and `term`.
+    pub distance: i64,
+    /// Log10 probability of the sentence including the edit penalty, higher is better.
+    pub score: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn ordering() {
+        let mut suggestions = [
+            Suggestion::new("there", 1, 300),
+            Suggestion::new("thee", 2, 1000),
+            Suggestion::new("their", 1, 300),
+            Suggestion::new("ther", 1, 500),
+            Suggestion::new("the", 0, 10),
+        ];
+        suggestions.sort();
+
+        let terms: Vec<&str> = suggestions.iter().map(|s| s.term.as_str()).collect();
+        assert_eq!(vec!["the", "ther", "their", "there", "thee"], terms);
+    }
+
+    #[test]
+    fn equality_and_hash() {
+        let mut with_confidence = Suggestion::new("their", 1, 300);
+        with_confidence.confidence = Some(0.5);
+
+        assert_ne!(
+            Suggestion::new("their", 1, 300),
+            Suggestion::new("there", 1, 300)
+        );
+        assert_eq!(Suggestion::new("their", 1, 300), with_confidence);
+
+        let set: HashSet<Suggestion> = [
+            Suggestion::new("their", 1, 300),
+            Suggestion::new("there", 1, 300),
+            with_confidence,
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(2, set.len());
+    }
+}
@@ -1,58 +1,187 @@
+use std::borrow::Cow;
 use std::cmp;
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::collections::hash_map::DefaultHasher;
 use std::fs::File;
-use std::hash::{Hash, Hasher};
 use std::io::{BufRead, BufReader};
+use std::mem;
 use std::path::Path;
 
-use crate::composition::Composition;
-use crate::edit_distance;
-use crate::string_strategy::StringStrategy;
-use crate::suggestion::Suggestion;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
-#[derive(Eq, PartialEq, Debug)]
+use crate::case_handling::CaseHandling;
+use crate::composition::Composition;
+use crate::delete_hasher::DeleteHasher;
+use crate::error::SymSpellError;
+use crate::error_model::Ranking;
+use crate::memory_usage::MemoryUsage;
+use crate::string_strategy::{Decoded, StringStrategy};
+use crate::suggestion::{ContextSuggestion, SentenceSuggestion, Suggestion};
+use crate::tokenizer::{Tokenizer, WhitespaceTokenizer};
+
+/// Controls the quantity/closeness of the suggestions returned by `lookup`.
+///
+/// Suggestions are sorted best first: by ascending edit distance, then
+/// descending count, then term (see `Suggestion`'s `Ord`). Results only
+/// depend on the input and the dictionary contents, not on the order the
+/// dictionary was loaded in or the `DeleteHasher`.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
 pub enum Verbosity {
+    /// The suggestion with the smallest edit distance and the highest count.
     Top,
+    /// All suggestions with the smallest edit distance found.
     Closest,
+    /// All suggestions within the maximum edit distance.
     All,
 }
 
+const DEFAULT_MAX_DICTIONARY_EDIT_DISTANCE: i64 = 2;
+const DEFAULT_PREFIX_LENGTH: i64 = 7;
+const DEFAULT_COUNT_THRESHOLD: i64 = 1;
+const DEFAULT_CORPUS_WORD_COUNT: i64 = 1_024_908_267_229;
+/// Stupid backoff factor applied when an n-gram is missing and the
+/// probability falls back to the next shorter n-gram.
+const BACKOFF_FACTOR: f64 = 0.4;
+/// Probability of a single edit, used to weigh the edit distance of
+/// suggestions against their language model probability.
+const EDIT_PROBABILITY: f64 = 0.1;
+/// Minimum beam width used to collect sentence alternatives.
+const MIN_ALTERNATIVES_BEAM_WIDTH: usize = 8;
+
 #[derive(derive_builder::Builder, PartialEq)]
+#[builder(build_fn(validate = "Self::validate"))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SymSpell<T: StringStrategy> {
     /// Maximum edit distance for doing lookups.
-    #[builder(default = "2")]
+    #[builder(default = "DEFAULT_MAX_DICTIONARY_EDIT_DISTANCE")]
     max_dictionary_edit_distance: i64,
     /// The length of word prefixes used for spell checking.
-    #[builder(default = "7")]
+    #[builder(default = "DEFAULT_PREFIX_LENGTH")]
     prefix_length: i64,
     /// The minimum frequency count for dictionary words to be considered correct spellings.
-    #[builder(default = "1")]
+    #[builder(default = "DEFAULT_COUNT_THRESHOLD")]
     count_threshold: i64,
-
-    //// number of all words in the corpus used to generate the
-    //// frequency dictionary. This is used to calculate the word
-    //// occurrence probability p from word counts c : p=c/N. N equals
-    //// the sum of all counts c in the dictionary only if the
-    //// dictionary is complete, but not if the dictionary is
-    //// truncated or filtered
-    #[builder(default = "1_024_908_267_229", setter(skip))]
+    /// Hash function used to key the delete index.
+    #[builder(default)]
+    delete_hasher: DeleteHasher,
+
+    /// Number of all words in the corpus used to generate the
+    /// frequency dictionary. This is used to calculate the word
+    /// occurrence probability p from word counts c : p=c/N. N equals
+    /// the sum of all counts c in the dictionary only if the
+    /// dictionary is complete, but not if the dictionary is
+    /// truncated or filtered. Defaults to the size of the Google
+    /// Books corpus the bundled English dictionary comes from.
+    #[builder(default = "DEFAULT_CORPUS_WORD_COUNT")]
     corpus_word_count: i64,
+    /// Use the sum of all loaded dictionary counts as `corpus_word_count`
+    /// instead of the configured value.
+    #[builder(default = "false")]
+    derive_corpus_word_count: bool,
+    /// Sum of the counts of all loaded dictionary entries, including the
+    /// ones below `count_threshold`.
+    #[builder(default = "0", setter(skip))]
+    total_word_count: i64,
 
     #[builder(default = "0", setter(skip))]
     max_length: i64,
+    /// Delete hashes mapped to the ids (positions in `terms`) of the words they originate from.
     #[builder(default = "HashMap::new()", setter(skip))]
-    deletes: HashMap<u64, Vec<Box<str>>>,
+    deletes: HashMap<u64, Vec<u32>>,
+    /// Word hashes mapped to word ids, probing the following hashes on collisions.
     #[builder(default = "HashMap::new()", setter(skip))]
-    words: HashMap<Box<str>, i64>,
+    words: HashMap<u64, u32>,
+    /// Every dictionary word stored once, indexed by word id.
+    #[builder(default = "Vec::new()", setter(skip))]
+    terms: Vec<Box<str>>,
+    /// Frequency count of every word, indexed by word id.
+    #[builder(default = "Vec::new()", setter(skip))]
+    counts: Vec<i64>,
     #[builder(default = "HashMap::new()", setter(skip))]
     bigrams: HashMap<Box<str>, i64>,
     #[builder(default = "i64::MAX", setter(skip))]
     bigram_min_count: i64,
-    #[builder(default = "T::new()", setter(skip))]
+    /// N-grams of three or more words with their counts.
+    #[builder(default = "HashMap::new()", setter(skip))]
+    ngrams: HashMap<Box<str>, i64>,
+    /// Highest n-gram order loaded, used by `sentence_score`.
+    #[builder(default = "1", setter(skip))]
+    max_ngram_order: usize,
+    /// String strategy used to prepare and split dictionary words and inputs.
+    #[builder(default = "T::default()")]
     string_strategy: T,
+    /// Case handling applied to dictionary words and inputs.
+    #[builder(default)]
+    case_handling: CaseHandling,
+    /// How `lookup` ranks its candidates.
+    #[builder(default)]
+    ranking: Ranking,
+    /// Set `Suggestion::confidence` on the results of `lookup`. Costs an
+    /// additional lookup of all candidates for `Verbosity::Top` and
+    /// `Verbosity::Closest`.
+    #[builder(default = "false")]
+    compute_confidence: bool,
+    /// Confidence (between 0 and 1) the bigram context must give an edit
+    /// distance 1 neighbour of a dictionary word before `lookup_compound`
+    /// replaces the word with it. Real-word errors are not corrected when unset.
+    #[builder(default, setter(strip_option))]
+    real_word_threshold: Option<f64>,
+    /// Minimum length of a term without a suggestion of its own at which
+    /// `lookup_compound` segments it into any number of words with the
+    /// `word_segmentation` algorithm and corrects those, instead of splitting
+    /// it in two. The term is still split in two when a word of the
+    /// segmentation has no suggestion. Terms are only split in two when unset.
+    #[builder(default, setter(strip_option))]
+    compound_segmentation_length: Option<usize>,
+    /// Dictionary casing and its count per lowercased word, used by `CaseHandling::Insensitive`.
+    #[builder(default = "HashMap::new()", setter(skip))]
+    display_forms: HashMap<Box<str>, (Box<str>, i64)>,
+}
+
+impl<T: StringStrategy> SymSpellBuilder<T> {
+    fn validate(&self) -> Result<(), String> {
+        let max_dictionary_edit_distance = self
+            .max_dictionary_edit_distance
+            .unwrap_or(DEFAULT_MAX_DICTIONARY_EDIT_DISTANCE);
+        let prefix_length = self.prefix_length.unwrap_or(DEFAULT_PREFIX_LENGTH);
+        let count_threshold = self.count_threshold.unwrap_or(DEFAULT_COUNT_THRESHOLD);
+        let corpus_word_count = self.corpus_word_count.unwrap_or(DEFAULT_CORPUS_WORD_COUNT);
+
+        if max_dictionary_edit_distance < 0 {
+            return Err(format!(
+                "max_dictionary_edit_distance ({}) must not be negative",
+                max_dictionary_edit_distance
+            ));
+        }
+        if prefix_length < 1 || prefix_length <= max_dictionary_edit_distance {
+            return Err(format!(
+                "prefix_length ({}) must be positive and bigger than max_dictionary_edit_distance ({})",
+                prefix_length, max_dictionary_edit_distance
+            ));
+        }
+        if count_threshold < 0 {
+            return Err(format!(
+                "count_threshold ({}) must not be negative",
+                count_threshold
+            ));
+        }
+        if corpus_word_count < 1 {
+            return Err(format!(
+                "corpus_word_count ({}) must be positive",
+                corpus_word_count
+            ));
+        }
+        if let Some(Some(threshold)) = self.real_word_threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(format!(
+                    "real_word_threshold ({}) must be between 0 and 1",
+                    threshold
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<T: StringStrategy> Default for SymSpell<T> {
@@ -62,6 +191,55 @@ impl<T: StringStrategy> Default for SymSpell<T> {
 }
 
 impl<T: StringStrategy> SymSpell<T> {
+    /// Maximum edit distance the dictionary was built for, and thus the
+    /// largest `max_edit_distance` lookups accept.
+    pub fn max_dictionary_edit_distance(&self) -> i64 {
+        self.max_dictionary_edit_distance
+    }
+
+    /// Number of words in the corpus used to calculate word probabilities.
+    ///
+    /// This is the sum of all loaded dictionary counts when
+    /// `derive_corpus_word_count` is enabled (and a dictionary has been
+    /// loaded), otherwise the configured `corpus_word_count`.
+    pub fn corpus_word_count(&self) -> i64 {
+        if self.derive_corpus_word_count && self.total_word_count > 0 {
+            self.total_word_count
+        } else {
+            self.corpus_word_count
+        }
+    }
+
+    /// String strategy used to prepare and split dictionary words and inputs.
+    pub fn string_strategy(&self) -> &T {
+        &self.string_strategy
+    }
+
+    pub fn case_handling(&self) -> CaseHandling {
+        self.case_handling
+    }
+
+    pub fn ranking(&self) -> &Ranking {
+        &self.ranking
+    }
+
+    pub fn compute_confidence(&self) -> bool {
+        self.compute_confidence
+    }
+
+    pub fn real_word_threshold(&self) -> Option<f64> {
+        self.real_word_threshold
+    }
+
+    pub fn compound_segmentation_length(&self) -> Option<usize> {
+        self.compound_segmentation_length
+    }
+
+    /// Number of distinct words in the dictionary.
+    pub fn word_count(&self) -> usize {
+        self.terms.len()
+    }
+
     /// Load multiple dictionary entries from a file of word/frequency count pairs.
     ///
     /// # Arguments
@@ -108,15 +286,19 @@ impl<T: StringStrategy> SymSpell<T> {
     ) -> bool {
         let line_parts: Vec<&str> = line.split(separator).collect();
         if line_parts.len() >= 2 {
-            let key = self
+            let term = self
                 .string_strategy
                 .prepare(line_parts[term_index as usize]);
             let count = match line_parts[count_index as usize].parse::<i64>() {
                 Ok(c) => c,
                 Err(_) => return false,
             };
+            let key = self.case_handling.apply(&term);
 
-            self.create_dictionary_entry(key, count);
+            if self.case_handling == CaseHandling::Insensitive {
+                self.add_display_form(&key, &term, count);
+            }
+            self.create_dictionary_entry(key.into_owned(), count);
         }
         true
     }
@@ -135,6 +317,58 @@ impl<T: StringStrategy> SymSpell<T> {
         term_index: i64,
         count_index: i64,
         separator: &str,
+    ) -> bool {
+        self.load_ngram_dictionary(corpus, 2, term_index, count_index, separator)
+    }
+
+    /// Load single dictionary entry from bigram/frequency count pair.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - bigram/frequency pair.
+    /// * `term_index` - The column position of the word.
+    /// * `count_index` - The column position of the frequency count.
+    /// * `separator` - Separator between word and frequency
+    pub fn load_bigram_dictionary_line(
+        &mut self,
+        line: &str,
+        term_index: i64,
+        count_index: i64,
+        separator: &str,
+    ) -> bool {
+        self.load_ngram_dictionary_line(line, 2, term_index, count_index, separator)
+    }
+
+    /// Load multiple n-gram entries from a file of n-gram/frequency count pairs.
+    ///
+    /// N-grams of order 3 and above are only used by the language model of
+    /// [`SymSpell::sentence_score`], [`SymSpell::lookup_compound_beam`] and
+    /// [`SymSpell::lookup_compound_top_k`].
+    ///
+    /// # Arguments
+    ///
+    /// * `corpus` - The path+filename of the file.
+    /// * `order` - The number of words per n-gram, at least 2.
+    /// * `term_index` - The column position of the first word.
+    /// * `count_index` - The column position of the frequency count.
+    /// * `separator` - Separator between words and frequency
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symspell::{SymSpell, UnicodeStringStrategy};
+    ///
+    /// let mut symspell: SymSpell<UnicodeStringStrategy> = SymSpell::default();
+    /// symspell.load_ngram_dictionary_line("i want to 120", 3, 0, 3, " ");
+    /// symspell.load_ngram_dictionary_line("want to buy\t80", 3, 0, 1, "\t");
+    /// ```
+    pub fn load_ngram_dictionary(
+        &mut self,
+        corpus: &str,
+        order: usize,
+        term_index: i64,
+        count_index: i64,
+        separator: &str,
     ) -> bool {
         if !Path::new(corpus).exists() {
             return false;
@@ -143,47 +377,60 @@ impl<T: StringStrategy> SymSpell<T> {
         let sr = BufReader::new(file);
         for line in sr.lines() {
             let line_str = line.unwrap();
-            self.load_bigram_dictionary_line(&line_str, term_index, count_index, separator);
+            self.load_ngram_dictionary_line(&line_str, order, term_index, count_index, separator);
         }
         true
     }
 
-    /// Load single dictionary entry from bigram/frequency count pair.
+    /// Load single n-gram entry from n-gram/frequency count pair.
+    ///
+    /// With a space separator the n-gram spans `order` columns starting at
+    /// `term_index`, otherwise it is the single column at `term_index`.
+    /// Returns false for n-grams without exactly `order` space separated words.
     ///
     /// # Arguments
     ///
-    /// * `line` - bigram/frequency pair.
-    /// * `term_index` - The column position of the word.
+    /// * `line` - n-gram/frequency pair.
+    /// * `order` - The number of words per n-gram, at least 2.
+    /// * `term_index` - The column position of the first word.
     /// * `count_index` - The column position of the frequency count.
-    /// * `separator` - Separator between word and frequency
-    pub fn load_bigram_dictionary_line(
+    /// * `separator` - Separator between words and frequency
+    pub fn load_ngram_dictionary_line(
         &mut self,
         line: &str,
+        order: usize,
         term_index: i64,
         count_index: i64,
         separator: &str,
     ) -> bool {
+        if order < 2 {
+            return false;
+        }
         let line_parts: Vec<&str> = line.split(separator).collect();
-        let line_parts_len = if separator == " " { 3 } else { 2 };
+        let line_parts_len = if separator == " " { order + 1 } else { 2 };
         if line_parts.len() >= line_parts_len {
             let key = if separator == " " {
-                self.string_strategy.prepare(&format!(
-                    "{} {}",
-                    line_parts[term_index as usize],
-                    line_parts[(term_index + 1) as usize]
-                ))
+                let term_index = term_index as usize;
+                self.prepare(&line_parts[term_index..term_index + order].join(" "))
             } else {
-                self.string_strategy
-                    .prepare(line_parts[term_index as usize])
+                self.prepare(line_parts[term_index as usize])
             };
+            if key.split(' ').count() != order {
+                return false;
+            }
             let count = match line_parts[count_index as usize].parse::<i64>() {
                 Ok(c) => c,
                 Err(_) => return false,
             };
-            self.bigrams.insert(key.into_boxed_str(), count);
-            if count < self.bigram_min_count {
-                self.bigram_min_count = count;
+            if order == 2 {
+                self.bigrams.insert(key.into_boxed_str(), count);
+                if count < self.bigram_min_count {
+                    self.bigram_min_count = count;
+                }
+            } else {
+                self.ngrams.insert(key.into_boxed_str(), count);
             }
+            self.max_ngram_order = cmp::max(self.max_ngram_order, order);
         }
         true
     }
@@ -206,31 +453,123 @@ impl<T: StringStrategy> SymSpell<T> {
     /// symspell.load_dictionary("data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
     /// symspell.lookup("whatver", Verbosity::Top, 2);
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_edit_distance` is bigger than `max_dictionary_edit_distance`,
+    /// see [`SymSpell::try_lookup`] for a non-panicking version.
     pub fn lookup(
         &self,
         input: &str,
         verbosity: Verbosity,
         max_edit_distance: i64,
     ) -> Vec<Suggestion> {
-        if max_edit_distance > self.max_dictionary_edit_distance {
-            panic!("max_edit_distance is bigger than max_dictionary_edit_distance");
+        self.try_lookup(input, verbosity, max_edit_distance)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Find suggested spellings for a given input word, like [`SymSpell::lookup`],
+    /// but return an error instead of panicking on invalid arguments.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The word being spell checked.
+    /// * `verbosity` - The value controlling the quantity/closeness of the retuned suggestions.
+    /// * `max_edit_distance` - The maximum edit distance between input and suggested words.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symspell::{SymSpell, AsciiStringStrategy, Verbosity};
+    ///
+    /// let mut symspell: SymSpell<AsciiStringStrategy> = SymSpell::default();
+    /// symspell.load_dictionary("data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+    /// assert!(symspell.try_lookup("whatver", Verbosity::Top, 2).is_ok());
+    /// assert!(symspell.try_lookup("whatver", Verbosity::Top, 3).is_err());
+    /// ```
+    pub fn try_lookup(
+        &self,
+        input: &str,
+        verbosity: Verbosity,
+        max_edit_distance: i64,
+    ) -> Result<Vec<Suggestion>, SymSpellError> {
+        self.check_max_edit_distance(max_edit_distance)?;
+
+        let mut suggestions = match self.ranking {
+            Ranking::Distance => self.lookup_terms(input, verbosity, max_edit_distance),
+            Ranking::NoisyChannel(_) => {
+                self.lookup_noisy_channel(input, verbosity, max_edit_distance)
+            }
+        };
+
+        if self.compute_confidence && !suggestions.is_empty() {
+            self.set_confidence(input, verbosity, max_edit_distance, &mut suggestions);
+        }
+        if self.case_handling == CaseHandling::Insensitive {
+            for suggestion in &mut suggestions {
+                if let Some((form, _)) = self.display_forms.get(suggestion.term.as_str()) {
+                    suggestion.term = form.to_string();
+                }
+            }
+        }
+        Ok(suggestions)
+    }
+
+    /// `lookup_terms` ranking by `P(input | word) * P(word)`. `Verbosity::Top`
+    /// picks the best of all candidates, `Verbosity::Closest` ranks the
+    /// candidates with the smallest edit distance.
+    fn lookup_noisy_channel(
+        &self,
+        input: &str,
+        verbosity: Verbosity,
+        max_edit_distance: i64,
+    ) -> Vec<Suggestion> {
+        let candidates_verbosity = match verbosity {
+            Verbosity::Closest => Verbosity::Closest,
+            Verbosity::Top | Verbosity::All => Verbosity::All,
+        };
+        let prepared = self.prepare(input);
+
+        let mut scored: Vec<(f64, Suggestion)> = self
+            .lookup_terms(input, candidates_verbosity, max_edit_distance)
+            .into_iter()
+            .map(|suggestion| (self.channel_weight(&prepared, &suggestion), suggestion))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+        if verbosity == Verbosity::Top {
+            scored.truncate(1);
         }
+        scored
+            .into_iter()
+            .map(|(_, suggestion)| suggestion)
+            .collect()
+    }
 
+    /// `lookup` without argument checks, returning suggestions as stored in the
+    /// dictionary.
+    fn lookup_terms(
+        &self,
+        input: &str,
+        verbosity: Verbosity,
+        max_edit_distance: i64,
+    ) -> Vec<Suggestion> {
         let mut suggestions: Vec<Suggestion> = Vec::new();
 
-        let prep_input = self.string_strategy.prepare(input);
+        let prep_input = self.prepare(input);
         let input = prep_input.as_str();
-        let input_len = self.string_strategy.len(input) as i64;
+        let mut input_offsets = Vec::new();
+        let input_chars = self.string_strategy.decode(input, &mut input_offsets);
+        let input_len = input_chars.len() as i64;
 
         if input_len - self.max_dictionary_edit_distance > self.max_length {
             return suggestions;
         }
 
         let mut hashset1: HashSet<String> = HashSet::new();
-        let mut hashset2: HashSet<String> = HashSet::new();
+        let mut hashset2: HashSet<u32> = HashSet::new();
 
-        if self.words.contains_key(input) {
-            let suggestion_count = self.words[input];
+        if let Some(suggestion_count) = self.word_frequency(input) {
             suggestions.push(Suggestion::new(input, 0, suggestion_count));
 
             if verbosity != Verbosity::All {
@@ -238,28 +577,30 @@ impl<T: StringStrategy> SymSpell<T> {
             }
         }
 
-        hashset2.insert(input.to_string());
-
         let mut max_edit_distance2 = max_edit_distance;
         let mut candidate_pointer = 0;
         let mut candidates = Vec::new();
+        // scratch buffers reused for every candidate and suggestion
+        let mut candidate_offsets = Vec::new();
+        let mut suggestion_offsets = Vec::new();
+        let mut delete = String::new();
 
         let mut input_prefix_len = input_len;
 
         if input_prefix_len > self.prefix_length {
             input_prefix_len = self.prefix_length;
-            candidates.push(
-                self.string_strategy
-                    .slice(input, 0, input_prefix_len as usize),
-            );
+            candidates.push(input_chars.slice(0, input_prefix_len as usize).to_string());
         } else {
             candidates.push(input.to_string());
         }
 
         while candidate_pointer < candidates.len() {
-            let candidate = candidates[candidate_pointer].clone();
+            let candidate = mem::take(&mut candidates[candidate_pointer]);
             candidate_pointer += 1;
-            let candidate_len = self.string_strategy.len(&candidate) as i64;
+            let candidate_chars = self
+                .string_strategy
+                .decode(&candidate, &mut candidate_offsets);
+            let candidate_len = candidate_chars.len() as i64;
             let length_diff = input_prefix_len - candidate_len;
 
             if length_diff > max_edit_distance2 {
@@ -270,14 +611,18 @@ impl<T: StringStrategy> SymSpell<T> {
             }
 
             if let Some(dict_suggestions) = self.deletes.get(&self.get_string_hash(&candidate)) {
-
-                for suggestion in dict_suggestions {
-                    let suggestion_len = self.string_strategy.len(suggestion) as i64;
+                for &suggestion_id in dict_suggestions {
+                    let suggestion = &self.terms[suggestion_id as usize];
 
                     if suggestion.as_ref() == input {
                         continue;
                     }
 
+                    let suggestion_chars = self
+                        .string_strategy
+                        .decode(suggestion, &mut suggestion_offsets);
+                    let suggestion_len = suggestion_chars.len() as i64;
+
                     if (suggestion_len - input_len).abs() > max_edit_distance2
                         || suggestion_len < candidate_len
                         || (suggestion_len == candidate_len && suggestion.as_ref() != candidate.as_str())
@@ -293,55 +638,49 @@ impl<T: StringStrategy> SymSpell<T> {
                         continue;
                     }
 
+                    // buckets are keyed by hash only, so a colliding delete
+                    // could bring in words the candidate isn't a delete of
+                    if !self.delete_in_suggestion_prefix(&candidate_chars, &suggestion_chars) {
+                        continue;
+                    }
+
                     let distance;
 
                     if candidate_len == 0 {
                         distance = cmp::max(input_len, suggestion_len);
 
-                        if distance > max_edit_distance2 || hashset2.contains(suggestion.as_ref()) {
+                        if distance > max_edit_distance2 || hashset2.contains(&suggestion_id) {
                             continue;
                         }
-                        hashset2.insert(suggestion.to_string());
+                        hashset2.insert(suggestion_id);
                     } else if suggestion_len == 1 {
-                        distance = if !input.contains(&self.string_strategy.slice(suggestion, 0, 1))
-                        {
+                        distance = if !input.contains(suggestion.as_ref()) {
                             input_len
                         } else {
                             input_len - 1
                         };
 
-                        if distance > max_edit_distance2 || hashset2.contains(suggestion.as_ref()) {
+                        if distance > max_edit_distance2 || hashset2.contains(&suggestion_id) {
                             continue;
                         }
 
-                        hashset2.insert(suggestion.to_string());
+                        hashset2.insert(suggestion_id);
                     } else if self.has_different_suffix(
                         max_edit_distance,
-                        input,
-                        input_len,
+                        &input_chars,
                         candidate_len,
-                        suggestion,
-                        suggestion_len,
+                        &suggestion_chars,
                     ) {
                         continue;
                     } else {
-                        if verbosity != Verbosity::All
-                            && !self.delete_in_suggestion_prefix(
-                                &candidate,
-                                candidate_len,
-                                suggestion,
-                                suggestion_len,
-                            )
-                        {
-                            continue;
-                        }
-
-                        if hashset2.contains(suggestion.as_ref()) {
+                        if hashset2.contains(&suggestion_id) {
                             continue;
                         }
-                        hashset2.insert(suggestion.to_string());
+                        hashset2.insert(suggestion_id);
 
-                        distance = edit_distance::distance(input, suggestion, max_edit_distance2);
+                        distance =
+                            self.string_strategy
+                                .distance(input, suggestion, max_edit_distance2);
 
                         if distance < 0 {
                             continue;
@@ -349,10 +688,11 @@ impl<T: StringStrategy> SymSpell<T> {
                     }
 
                     if distance <= max_edit_distance2 {
-                        let suggestion_count = self.words[suggestion];
+                        let suggestion_count = self.counts[suggestion_id as usize];
                         let si = Suggestion::new(suggestion.as_ref(), distance, suggestion_count);
 
                         if !suggestions.is_empty() {
+                            #[allow(clippy::collapsible_match)]
                             match verbosity {
                                 Verbosity::Closest => {
                                     if distance < max_edit_distance2 {
@@ -360,9 +700,9 @@ impl<T: StringStrategy> SymSpell<T> {
                                     }
                                 }
                                 Verbosity::Top => {
-                                    if distance < max_edit_distance2
-                                        || suggestion_count > suggestions[0].count
-                                    {
+                                    // compare by `Suggestion`'s order, so ties on distance
+                                    // and count don't depend on the order words are found
+                                    if si < suggestions[0] {
                                         max_edit_distance2 = distance;
                                         suggestions[0] = si;
                                     }
@@ -386,12 +726,12 @@ impl<T: StringStrategy> SymSpell<T> {
                     continue;
                 }
 
-                for i in 0..candidate_len {
-                    let delete = self.string_strategy.remove(&candidate, i as usize);
+                for i in 0..candidate_chars.len() {
+                    candidate_chars.remove_into(i, &mut delete);
 
                     if !hashset1.contains(&delete) {
                         hashset1.insert(delete.clone());
-                        candidates.push(delete);
+                        candidates.push(delete.clone());
                     }
                 }
             }
@@ -404,9 +744,142 @@ impl<T: StringStrategy> SymSpell<T> {
         suggestions
     }
 
+    /// Find suggested spellings for a given input word, ranked by how well they fit
+    /// between the neighbouring words.
+    ///
+    /// Every suggestion within `max_edit_distance` is scored with the bigram
+    /// probabilities `P(word | prev_word)` and `P(next_word | word)`, backing off
+    /// to unigram probabilities for bigrams missing from the bigram dictionary,
+    /// and with a penalty per edit. Neighbours missing from the dictionary are
+    /// ignored. Suggestions are sorted by descending score.
+    ///
+    /// # Arguments
+    ///
+    /// * `prev_word` - The word before `input`, if any.
+    /// * `input` - The word being spell checked.
+    /// * `next_word` - The word after `input`, if any.
+    /// * `max_edit_distance` - The maximum edit distance between input and suggested words.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symspell::{SymSpell, UnicodeStringStrategy};
+    ///
+    /// let mut symspell: SymSpell<UnicodeStringStrategy> = SymSpell::default();
+    /// symspell.load_dictionary("data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+    /// symspell.load_bigram_dictionary_line("their house 1000", 0, 2, " ");
+    /// symspell.lookup_in_context(Some("in"), "ther", Some("house"), 2);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_edit_distance` is bigger than `max_dictionary_edit_distance`,
+    /// see [`SymSpell::try_lookup_in_context`] for a non-panicking version.
+    pub fn lookup_in_context(
+        &self,
+        prev_word: Option<&str>,
+        input: &str,
+        next_word: Option<&str>,
+        max_edit_distance: i64,
+    ) -> Vec<ContextSuggestion> {
+        self.try_lookup_in_context(prev_word, input, next_word, max_edit_distance)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Find suggested spellings for a given input word in context, like
+    /// [`SymSpell::lookup_in_context`], but return an error instead of panicking
+    /// on invalid arguments.
+    ///
+    /// # Arguments
+    ///
+    /// * `prev_word` - The word before `input`, if any.
+    /// * `input` - The word being spell checked.
+    /// * `next_word` - The word after `input`, if any.
+    /// * `max_edit_distance` - The maximum edit distance between input and suggested words.
+    pub fn try_lookup_in_context(
+        &self,
+        prev_word: Option<&str>,
+        input: &str,
+        next_word: Option<&str>,
+        max_edit_distance: i64,
+    ) -> Result<Vec<ContextSuggestion>, SymSpellError> {
+        self.check_max_edit_distance(max_edit_distance)?;
+
+        let prev_word = prev_word.map(|w| self.prepare(w));
+        let next_word = next_word.map(|w| self.prepare(w));
+
+        let mut suggestions: Vec<ContextSuggestion> = self
+            .lookup_terms(input, Verbosity::All, max_edit_distance)
+            .into_iter()
+            .map(|suggestion| ContextSuggestion {
+                score: self.context_score(prev_word.as_deref(), &suggestion, next_word.as_deref()),
+                suggestion,
+            })
+            .collect();
+
+        suggestions.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.suggestion.cmp(&b.suggestion))
+        });
+
+        for suggestion in &mut suggestions {
+            if let Some((form, _)) = self.display_forms.get(suggestion.suggestion.term.as_str()) {
+                suggestion.suggestion.term = form.to_string();
+            }
+        }
+        Ok(suggestions)
+    }
+
+    /// Log10 probability of a sentence under the loaded n-gram language model.
+    ///
+    /// Every word is scored given up to the `n - 1` preceding words, where `n`
+    /// is the highest n-gram order loaded, with stupid backoff to shorter
+    /// n-grams when an n-gram is missing. Words missing from the dictionary
+    /// get the probability `lookup_compound` assigns to unknown words. Useful
+    /// to compare candidate sentences, e.g. from `lookup_compound` and
+    /// `word_segmentation`.
+    ///
+    /// The model is used by this method, [`SymSpell::lookup_compound_beam`] and
+    /// [`SymSpell::lookup_compound_top_k`] only. `lookup_compound` ranks its
+    /// splits with bigram counts and `word_segmentation` with unigram counts,
+    /// n-grams of a higher order don't affect either.
+    ///
+    /// # Arguments
+    ///
+    /// * `sentence` - The words to score, separated by whitespace.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symspell::{SymSpell, UnicodeStringStrategy};
+    ///
+    /// let mut symspell: SymSpell<UnicodeStringStrategy> = SymSpell::default();
+    /// symspell.load_dictionary("data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+    /// symspell.load_bigram_dictionary_line("want to 100", 0, 2, " ");
+    /// symspell.load_ngram_dictionary_line("want to buy 80", 3, 0, 3, " ");
+    /// assert!(symspell.sentence_score("want to buy") > symspell.sentence_score("want to by"));
+    /// ```
+    pub fn sentence_score(&self, sentence: &str) -> f64 {
+        let prepared = self.prepare(sentence);
+        let words: Vec<&str> = prepared.split_whitespace().collect();
+
+        (0..words.len())
+            .map(|i| {
+                let start = (i + 1).saturating_sub(self.max_ngram_order);
+                self.ngram_probability(&words[start..=i]).log10()
+            })
+            .sum()
+    }
+
     /// Find suggested spellings for a given input sentence, using the maximum
     /// edit distance specified during construction of the SymSpell dictionary.
     ///
+    /// Returns a single suggestion. Its count is the product of the word
+    /// probabilities scaled by `corpus_word_count`; see
+    /// [`SymSpell::lookup_compound_top_k`] for several alternatives with a
+    /// comparable score.
+    ///
     /// # Arguments
     ///
     /// * `input` - The sentence being spell checked.
@@ -421,23 +894,73 @@ impl<T: StringStrategy> SymSpell<T> {
     /// symspell.load_dictionary("data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
     /// symspell.lookup_compound("whereis th elove", 2);
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `edit_distance_max` is bigger than `max_dictionary_edit_distance`,
+    /// see [`SymSpell::try_lookup_compound`] for a non-panicking version.
     pub fn lookup_compound(&self, input: &str, edit_distance_max: i64) -> Vec<Suggestion> {
+        self.lookup_compound_with_tokenizer(input, edit_distance_max, &WhitespaceTokenizer)
+    }
+
+    /// Find suggested spellings for a given input sentence, like [`SymSpell::lookup_compound`],
+    /// but split the sentence into terms with the given tokenizer instead of
+    /// [`WhitespaceTokenizer`].
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The sentence being spell checked.
+    /// * `edit_distance_max` - The maximum edit distance between input and suggested words.
+    /// * `tokenizer` - Splits the prepared sentence into terms.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symspell::{SymSpell, UnicodeStringStrategy};
+    ///
+    /// let mut symspell: SymSpell<UnicodeStringStrategy> = SymSpell::default();
+    /// symspell.load_dictionary("data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+    ///
+    /// // treat hyphens like spaces
+    /// let tokenizer = |text: &str| -> Vec<String> {
+    ///     text.split(|c: char| c.is_whitespace() || c == '-')
+    ///         .filter(|s| !s.is_empty())
+    ///         .map(|s| s.to_string())
+    ///         .collect()
+    /// };
+    /// symspell.lookup_compound_with_tokenizer("well-knwn", 2, &tokenizer);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `edit_distance_max` is bigger than `max_dictionary_edit_distance`,
+    /// see [`SymSpell::try_lookup_compound_with_tokenizer`] for a non-panicking version.
+    pub fn lookup_compound_with_tokenizer<K: Tokenizer>(
+        &self,
+        input: &str,
+        edit_distance_max: i64,
+        tokenizer: &K,
+    ) -> Vec<Suggestion> {
+        self.check_max_edit_distance(edit_distance_max)
+            .unwrap_or_else(|e| panic!("{}", e));
+
         //parse input string into single terms
-        let term_list1 = self.parse_words(&self.string_strategy.prepare(input));
+        let term_list1 = tokenizer.tokenize(&self.prepare_sentence(input));
 
         // let mut suggestions_previous_term: Vec<Suggestion> = Vec::new();                  //suggestions for a single term
         let mut suggestions: Vec<Suggestion>;
         let mut suggestion_parts: Vec<Suggestion> = Vec::new();
+        let mut term_offsets = Vec::new();
 
         //translate every term to its best suggestion, otherwise it remains unchanged
         let mut last_combi = false;
 
         for (i, term) in term_list1.iter().enumerate() {
-            suggestions = self.lookup(term, Verbosity::Top, edit_distance_max);
+            suggestions = self.lookup_terms(term, Verbosity::Top, edit_distance_max);
 
             //combi check, always before split
             if i > 0 && !last_combi {
-                let mut suggestions_combi: Vec<Suggestion> = self.lookup(
+                let mut suggestions_combi: Vec<Suggestion> = self.lookup_terms(
                     &format!("{}{}", term_list1[i - 1], term_list1[i]),
                     Verbosity::Top,
                     edit_distance_max,
@@ -451,7 +974,7 @@ impl<T: StringStrategy> SymSpell<T> {
                         Suggestion::new(
                             term_list1[1].as_str(),
                             edit_distance_max + 1,
-                            10 / (10i64).pow(self.string_strategy.len(&term_list1[i]) as u32),
+                            10 / (10i64).pow(self.char_len(&term_list1[i]) as u32),
                         )
                     };
 
@@ -462,7 +985,7 @@ impl<T: StringStrategy> SymSpell<T> {
                         && (suggestions_combi[0].distance + 1 < distance1
                             || (suggestions_combi[0].distance + 1 == distance1
                                 && (suggestions_combi[0].count
-                                    > best1.count / self.corpus_word_count * best2.count)))
+                                    > best1.count / self.corpus_word_count() * best2.count)))
                     {
                         suggestions_combi[0].distance += 1;
                         let last_i = suggestion_parts.len() - 1;
@@ -476,11 +999,20 @@ impl<T: StringStrategy> SymSpell<T> {
 
             //alway split terms without suggestion / never split terms with suggestion ed=0 / never split single char terms
             if !suggestions.is_empty()
-                && ((suggestions[0].distance == 0)
-                    || (self.string_strategy.len(&term_list1[i]) == 1))
+                && ((suggestions[0].distance == 0) || (self.char_len(&term_list1[i]) == 1))
             {
-                //choose best suggestion
-                suggestion_parts.push(suggestions[0].clone());
+                //choose best suggestion, in context when it is a dictionary word
+                let suggestion = if suggestions[0].distance == 0 {
+                    let prev_word = suggestion_parts
+                        .last()
+                        .and_then(|part| part.term.rsplit(' ').next());
+                    let next_word = term_list1.get(i + 1).map(|term| term.as_str());
+
+                    self.correct_real_word(prev_word, &suggestions[0], next_word)
+                } else {
+                    suggestions[0].clone()
+                };
+                suggestion_parts.push(suggestion);
             } else {
                 let mut suggestion_split_best = if !suggestions.is_empty() {
                     //add original term
@@ -490,27 +1022,40 @@ impl<T: StringStrategy> SymSpell<T> {
                     Suggestion::empty()
                 };
 
-                let term_length = self.string_strategy.len(&term_list1[i]);
-
-                if term_length > 1 {
+                let term = self
+                    .string_strategy
+                    .decode(&term_list1[i], &mut term_offsets);
+                let term_length = term.len();
+
+                //segment long unknown terms into any number of words, split
+                //them in two when a segment has no suggestion
+                if suggestions.is_empty()
+                    && self
+                        .compound_segmentation_length
+                        .is_some_and(|length| term_length >= length)
+                    && let Some(segmented) = self.segment_term(&term, edit_distance_max)
+                {
+                    suggestion_parts.push(segmented);
+                } else if term_length > 1 {
                     for j in 1..term_length {
-                        let part1 = self.string_strategy.slice(&term_list1[i], 0, j);
-                        let part2 = self.string_strategy.slice(&term_list1[i], j, term_length);
+                        let part1 = term.slice(0, j);
+                        let part2 = term.suffix(j);
 
                         let mut suggestion_split = Suggestion::empty();
 
-                        let suggestions1 = self.lookup(&part1, Verbosity::Top, edit_distance_max);
+                        let suggestions1 =
+                            self.lookup_terms(part1, Verbosity::Top, edit_distance_max);
 
                         if !suggestions1.is_empty() {
                             let suggestions2 =
-                                self.lookup(&part2, Verbosity::Top, edit_distance_max);
+                                self.lookup_terms(part2, Verbosity::Top, edit_distance_max);
 
                             if !suggestions2.is_empty() {
                                 //select best suggestion for split pair
                                 suggestion_split.term =
                                     format!("{} {}", suggestions1[0].term, suggestions2[0].term);
 
-                                let mut distance2 = edit_distance::distance(
+                                let mut distance2 = self.string_strategy.distance(
                                     &term_list1[i],
                                     &format!("{} {}", suggestions1[0].term, suggestions2[0].term),
                                     edit_distance_max,
@@ -580,7 +1125,7 @@ impl<T: StringStrategy> SymSpell<T> {
                                         cmp::min(
                                             self.bigram_min_count,
                                             ((suggestions1[0].count as f64)
-                                                / (self.corpus_word_count as f64)
+                                                / (self.corpus_word_count() as f64)
                                                 * (suggestions2[0].count as f64))
                                                 as i64,
                                         )
@@ -606,9 +1151,7 @@ impl<T: StringStrategy> SymSpell<T> {
                         let mut si = Suggestion::empty();
                         // NOTE: this effectively clamps si_count to a certain minimum value, which it can't go below
                         let si_count: f64 = 10f64
-                            / ((10i64)
-                                .saturating_pow(self.string_strategy.len(&term_list1[i]) as u32))
-                                as f64;
+                            / ((10i64).saturating_pow(self.char_len(&term_list1[i]) as u32)) as f64;
 
                         si.term = term_list1[i].clone();
                         si.count = si_count as i64;
@@ -619,8 +1162,7 @@ impl<T: StringStrategy> SymSpell<T> {
                     let mut si = Suggestion::empty();
                     // NOTE: this effectively clamps si_count to a certain minimum value, which it can't go below
                     let si_count: f64 = 10f64
-                        / ((10i64).saturating_pow(self.string_strategy.len(&term_list1[i]) as u32))
-                            as f64;
+                        / ((10i64).saturating_pow(self.char_len(&term_list1[i]) as u32)) as f64;
 
                     si.term = term_list1[i].clone();
                     si.count = si_count as i64;
@@ -632,74 +1174,363 @@ impl<T: StringStrategy> SymSpell<T> {
 
         let mut suggestion = Suggestion::empty();
 
-        let mut tmp_count: f64 = self.corpus_word_count as f64;
+        let mut tmp_count: f64 = self.corpus_word_count() as f64;
 
         let mut s = "".to_string();
         for si in suggestion_parts {
-            s.push_str(&si.term);
-            s.push(' ');
-            tmp_count *= si.count as f64 / self.corpus_word_count as f64;
+            for word in si.term.split(' ') {
+                s.push_str(self.display_form(word));
+                s.push(' ');
+            }
+            tmp_count *= si.count as f64 / self.corpus_word_count() as f64;
         }
 
         suggestion.term = s.trim().to_string();
         suggestion.count = tmp_count as i64;
-        suggestion.distance = edit_distance::distance(input, &suggestion.term, 2i64.pow(31) - 1);
+        suggestion.distance =
+            self.string_strategy
+                .distance(input, &suggestion.term, 2i64.pow(31) - 1);
 
         vec![suggestion]
     }
 
-    /// Divides a string into words by inserting missing spaces at the appropriate positions
-    ///
+    /// Find suggested spellings for a given input sentence, like [`SymSpell::lookup_compound`],
+    /// but return an error instead of panicking on invalid arguments.
     ///
     /// # Arguments
     ///
-    /// * `input` - The word being segmented.
+    /// * `input` - The sentence being spell checked.
     /// * `max_edit_distance` - The maximum edit distance between input and suggested words.
+    pub fn try_lookup_compound(
+        &self,
+        input: &str,
+        edit_distance_max: i64,
+    ) -> Result<Vec<Suggestion>, SymSpellError> {
+        self.check_max_edit_distance(edit_distance_max)?;
+        Ok(self.lookup_compound(input, edit_distance_max))
+    }
+
+    /// Find suggested spellings for a given input sentence, like
+    /// [`SymSpell::lookup_compound_with_tokenizer`], but return an error
+    /// instead of panicking on invalid arguments.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The sentence being spell checked.
+    /// * `edit_distance_max` - The maximum edit distance between input and suggested words.
+    /// * `tokenizer` - Splits the prepared sentence into terms.
+    pub fn try_lookup_compound_with_tokenizer<K: Tokenizer>(
+        &self,
+        input: &str,
+        edit_distance_max: i64,
+        tokenizer: &K,
+    ) -> Result<Vec<Suggestion>, SymSpellError> {
+        self.check_max_edit_distance(edit_distance_max)?;
+        Ok(self.lookup_compound_with_tokenizer(input, edit_distance_max, tokenizer))
+    }
+
+    /// Find up to `k` alternative corrections of a sentence, best first.
+    ///
+    /// The alternatives are the result of [`SymSpell::lookup_compound`] and the
+    /// best sentences of [`SymSpell::lookup_compound_beam`], ranked by
+    ///
+    /// `score = log10(P(sentence)) + distance * log10(0.1)`
+    ///
+    /// where `P(sentence)` is the n-gram language model probability of
+    /// [`SymSpell::sentence_score`] and `distance` the edit distance between
+    /// the input and the alternative, so every edit makes an alternative ten
+    /// times less likely. `10^score` is a probability-like value that can be
+    /// compared between alternatives of the same input.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The sentence being spell checked.
+    /// * `edit_distance_max` - The maximum edit distance between input and suggested words.
+    /// * `k` - The maximum number of alternatives returned.
     ///
     /// # Examples
     ///
     /// ```
-    /// use symspell::{SymSpell, UnicodeStringStrategy, Verbosity};
+    /// use symspell::{SymSpell, UnicodeStringStrategy};
     ///
     /// let mut symspell: SymSpell<UnicodeStringStrategy> = SymSpell::default();
     /// symspell.load_dictionary("data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
-    /// symspell.word_segmentation("itwas", 2);
+    /// for alternative in symspell.lookup_compound_top_k("whereis th elove", 2, 3) {
+    ///     println!("{} {}", alternative.term, alternative.score);
+    /// }
     /// ```
-    pub fn word_segmentation(&self, input: &str, max_edit_distance: i64) -> Composition {
-        let input = self.string_strategy.prepare(input);
-        let asize = self.string_strategy.len(&input);
-
-        let mut ci: usize = 0;
+    ///
+    /// # Panics
+    ///
+    /// Panics if `edit_distance_max` is bigger than `max_dictionary_edit_distance`,
+    /// see [`SymSpell::try_lookup_compound_top_k`] for a non-panicking version.
+    pub fn lookup_compound_top_k(
+        &self,
+        input: &str,
+        edit_distance_max: i64,
+        k: usize,
+    ) -> Vec<SentenceSuggestion> {
+        self.try_lookup_compound_top_k(input, edit_distance_max, k)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Find up to `k` alternative corrections of a sentence, like
+    /// [`SymSpell::lookup_compound_top_k`], but return an error instead of
+    /// panicking on invalid arguments.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The sentence being spell checked.
+    /// * `edit_distance_max` - The maximum edit distance between input and suggested words.
+    /// * `k` - The maximum number of alternatives returned.
+    pub fn try_lookup_compound_top_k(
+        &self,
+        input: &str,
+        edit_distance_max: i64,
+        k: usize,
+    ) -> Result<Vec<SentenceSuggestion>, SymSpellError> {
+        self.check_max_edit_distance(edit_distance_max)?;
+
+        let greedy = self
+            .lookup_compound(input, edit_distance_max)
+            .into_iter()
+            .map(|suggestion| (suggestion.term, suggestion.distance));
+        let beam_width = cmp::max(k, MIN_ALTERNATIVES_BEAM_WIDTH);
+        let beam = self
+            .lookup_compound_beam(input, edit_distance_max, beam_width, k)
+            .into_iter()
+            .map(|suggestion| (suggestion.term, suggestion.distance));
+
+        let mut seen = HashSet::new();
+        let mut alternatives: Vec<SentenceSuggestion> = greedy
+            .chain(beam)
+            .filter(|(term, _)| seen.insert(term.clone()))
+            .map(|(term, distance)| SentenceSuggestion {
+                score: self.sentence_score(&term) + distance as f64 * EDIT_PROBABILITY.log10(),
+                term,
+                distance,
+            })
+            .collect();
+
+        alternatives.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.distance.cmp(&b.distance))
+                .then_with(|| a.term.cmp(&b.term))
+        });
+        alternatives.truncate(k);
+        Ok(alternatives)
+    }
+
+    /// Find the most probable corrections of a sentence with a beam search.
+    ///
+    /// Unlike [`SymSpell::lookup_compound`], which picks the best suggestion per
+    /// term from left to right, this keeps the closest suggestions of every
+    /// whitespace separated term and searches for the sentences with the highest
+    /// score under the n-gram language model (see [`SymSpell::sentence_score`]),
+    /// with a penalty per edit. Terms are neither split nor combined; terms
+    /// without suggestions are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The sentence being spell checked.
+    /// * `max_edit_distance` - The maximum edit distance between input and suggested words.
+    /// * `beam_width` - The number of partial sentences kept after every term.
+    /// * `n_best` - The maximum number of sentences returned, best first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symspell::{SymSpell, UnicodeStringStrategy};
+    ///
+    /// let mut symspell: SymSpell<UnicodeStringStrategy> = SymSpell::default();
+    /// symspell.load_dictionary("data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+    /// symspell.lookup_compound_beam("i want to bu a car", 2, 8, 3);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_edit_distance` is bigger than `max_dictionary_edit_distance`,
+    /// see [`SymSpell::try_lookup_compound_beam`] for a non-panicking version.
+    pub fn lookup_compound_beam(
+        &self,
+        input: &str,
+        max_edit_distance: i64,
+        beam_width: usize,
+        n_best: usize,
+    ) -> Vec<SentenceSuggestion> {
+        self.try_lookup_compound_beam(input, max_edit_distance, beam_width, n_best)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Find the most probable corrections of a sentence with a beam search, like
+    /// [`SymSpell::lookup_compound_beam`], but return an error instead of panicking
+    /// on invalid arguments.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The sentence being spell checked.
+    /// * `max_edit_distance` - The maximum edit distance between input and suggested words.
+    /// * `beam_width` - The number of partial sentences kept after every term.
+    /// * `n_best` - The maximum number of sentences returned, best first.
+    pub fn try_lookup_compound_beam(
+        &self,
+        input: &str,
+        max_edit_distance: i64,
+        beam_width: usize,
+        n_best: usize,
+    ) -> Result<Vec<SentenceSuggestion>, SymSpellError> {
+        self.check_max_edit_distance(max_edit_distance)?;
+
+        let candidates: Vec<Vec<Suggestion>> = WhitespaceTokenizer
+            .tokenize(&self.prepare_sentence(input))
+            .into_iter()
+            .map(|term| {
+                let suggestions = self.lookup_terms(&term, Verbosity::Closest, max_edit_distance);
+                if suggestions.is_empty() {
+                    vec![Suggestion::new(term, max_edit_distance + 1, 0)]
+                } else {
+                    suggestions
+                }
+            })
+            .collect();
+
+        let edit_penalty = EDIT_PROBABILITY.log10();
+        let mut beam: Vec<(Vec<&str>, f64)> = vec![(Vec::new(), 0.0)];
+
+        for term_candidates in &candidates {
+            let mut expanded = Vec::with_capacity(beam.len() * term_candidates.len());
+
+            for (words, score) in &beam {
+                for candidate in term_candidates {
+                    let mut words = words.clone();
+                    words.push(candidate.term.as_str());
+
+                    let start = words.len().saturating_sub(self.max_ngram_order);
+                    let score = score
+                        + self.ngram_probability(&words[start..]).log10()
+                        + candidate.distance as f64 * edit_penalty;
+                    expanded.push((words, score));
+                }
+            }
+
+            expanded.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            expanded.truncate(cmp::max(beam_width, 1));
+            beam = expanded;
+        }
+
+        beam.truncate(n_best);
+        Ok(beam
+            .into_iter()
+            .map(|(words, score)| {
+                let term = words
+                    .iter()
+                    .map(|word| self.display_form(word))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                SentenceSuggestion {
+                    distance: self
+                        .string_strategy
+                        .distance(input, &term, 2i64.pow(31) - 1),
+                    term,
+                    score,
+                }
+            })
+            .collect())
+    }
+
+    /// Divides a string into words by inserting missing spaces at the appropriate positions
+    ///
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The word being segmented.
+    /// * `max_edit_distance` - The maximum edit distance between input and suggested words.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symspell::{SymSpell, UnicodeStringStrategy, Verbosity};
+    ///
+    /// let mut symspell: SymSpell<UnicodeStringStrategy> = SymSpell::default();
+    /// symspell.load_dictionary("data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+    /// symspell.word_segmentation("itwas", 2);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_edit_distance` is bigger than `max_dictionary_edit_distance`,
+    /// see [`SymSpell::try_word_segmentation`] for a non-panicking version.
+    pub fn word_segmentation(&self, input: &str, max_edit_distance: i64) -> Composition {
+        self.check_max_edit_distance(max_edit_distance)
+            .unwrap_or_else(|e| panic!("{}", e));
+
+        let prep_input = self.prepare(input);
+        let mut input_offsets = Vec::new();
+        let input = self.string_strategy.decode(&prep_input, &mut input_offsets);
+        self.segment(&input, max_edit_distance)
+    }
+
+    /// Divides a string into words, like [`SymSpell::word_segmentation`],
+    /// but return an error instead of panicking on invalid arguments.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The word being segmented.
+    /// * `max_edit_distance` - The maximum edit distance between input and suggested words.
+    pub fn try_word_segmentation(
+        &self,
+        input: &str,
+        max_edit_distance: i64,
+    ) -> Result<Composition, SymSpellError> {
+        self.check_max_edit_distance(max_edit_distance)?;
+        Ok(self.word_segmentation(input, max_edit_distance))
+    }
+
+    /// The `word_segmentation` algorithm on a prepared input.
+    fn segment(&self, input: &Decoded, max_edit_distance: i64) -> Composition {
+        let asize = input.len();
+        if asize == 0 {
+            return Composition::empty();
+        }
+
+        let mut ci: usize = 0;
         let mut compositions: Vec<Composition> = vec![Composition::empty(); asize];
 
         for j in 0..asize {
             let imax = cmp::min(asize - j, self.max_length as usize);
             for i in 1..=imax {
-                let mut part = self.string_strategy.slice(&input, j, j + i);
+                let mut part = input.slice(j, j + i);
 
                 let mut sep_len = 0;
                 let mut top_ed: i64 = 0;
 
-                let first_char = self.string_strategy.at(&part, 0).unwrap();
-                if first_char.is_whitespace() {
-                    part = self.string_strategy.remove(&part, 0);
+                if input.at(j).unwrap().starts_with(char::is_whitespace) {
+                    part = input.slice(j + 1, j + i);
                 } else {
                     sep_len = 1;
                 }
 
                 top_ed += part.len() as i64;
 
-                part = part.replace(" ", "");
+                let mut part = if part.contains(' ') {
+                    Cow::Owned(part.replace(' ', ""))
+                } else {
+                    Cow::Borrowed(part)
+                };
 
                 top_ed -= part.len() as i64;
 
-                let results = self.lookup(&part, Verbosity::Top, max_edit_distance);
+                let results = self.lookup_terms(&part, Verbosity::Top, max_edit_distance);
 
                 let top_prob_log = if !results.is_empty() && results[0].distance == 0 {
-                    (results[0].count as f64 / self.corpus_word_count as f64).log10()
+                    if let Some((form, _)) = self.display_forms.get(&*part) {
+                        part = Cow::Owned(form.to_string());
+                    }
+                    (results[0].count as f64 / self.corpus_word_count() as f64).log10()
                 } else {
                     top_ed += part.len() as i64;
-                    (10.0 / (self.corpus_word_count as f64 * 10.0f64.powf(part.len() as f64)))
+                    (10.0 / (self.corpus_word_count() as f64 * 10.0f64.powf(part.len() as f64)))
                         .log10()
                 };
 
@@ -707,7 +1538,7 @@ impl<T: StringStrategy> SymSpell<T> {
                 // set values in first loop
                 if j == 0 {
                     compositions[i - 1] = Composition {
-                        segmented_string: part.to_owned(),
+                        segmented_string: part.to_string(),
                         distance_sum: top_ed,
                         prob_log_sum: top_prob_log,
                     };
@@ -735,33 +1566,192 @@ impl<T: StringStrategy> SymSpell<T> {
         compositions[ci].to_owned()
     }
 
-    fn delete_in_suggestion_prefix(
+    /// Segments a term of `lookup_compound` into several words and corrects
+    /// each of them. Returns `None` unless every word has a suggestion.
+    fn segment_term(&self, term: &Decoded, edit_distance_max: i64) -> Option<Suggestion> {
+        let composition = self.segment(term, edit_distance_max);
+
+        let mut words = Vec::new();
+        let mut count = self.corpus_word_count() as f64;
+        for part in composition.segmented_string.split_whitespace() {
+            let suggestion = self
+                .lookup_terms(part, Verbosity::Top, edit_distance_max)
+                .into_iter()
+                .next()?;
+            // Naive Bayes probability of the word combination, like for two word splits
+            count *= suggestion.count as f64 / self.corpus_word_count() as f64;
+            words.push(suggestion.term);
+        }
+        if words.len() < 2 {
+            return None;
+        }
+
+        let term_segmented = words.join(" ");
+        Some(Suggestion::new(
+            term_segmented.as_str(),
+            self.string_strategy
+                .distance(term.as_str(), &term_segmented, 2i64.pow(31) - 1),
+            count as i64,
+        ))
+    }
+
+    /// Estimate the heap memory held by the dictionary, delete index and bigrams.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symspell::{SymSpell, UnicodeStringStrategy};
+    ///
+    /// let mut symspell: SymSpell<UnicodeStringStrategy> = SymSpell::default();
+    /// symspell.load_dictionary("data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+    /// println!("{} bytes", symspell.memory_usage().total());
+    /// ```
+    pub fn memory_usage(&self) -> MemoryUsage {
+        fn map_size<K, V>(map: &HashMap<K, V>) -> usize {
+            map.capacity() * (mem::size_of::<K>() + mem::size_of::<V>() + 1)
+        }
+
+        MemoryUsage {
+            words: map_size(&self.words)
+                + self.counts.capacity() * mem::size_of::<i64>()
+                + map_size(&self.display_forms)
+                + self
+                    .display_forms
+                    .iter()
+                    .map(|(k, (form, _))| k.len() + form.len())
+                    .sum::<usize>(),
+            terms: self.terms.capacity() * mem::size_of::<Box<str>>()
+                + self.terms.iter().map(|t| t.len()).sum::<usize>(),
+            deletes: map_size(&self.deletes)
+                + self
+                    .deletes
+                    .values()
+                    .map(|ids| ids.capacity() * mem::size_of::<u32>())
+                    .sum::<usize>(),
+            bigrams: map_size(&self.bigrams)
+                + self.bigrams.keys().map(|k| k.len()).sum::<usize>()
+                + map_size(&self.ngrams)
+                + self.ngrams.keys().map(|k| k.len()).sum::<usize>(),
+        }
+    }
+
+    /// Find suggested spellings for multiple input words at once.
+    ///
+    /// Identical inputs are looked up only once. With the `rayon` feature
+    /// enabled, the lookups are spread across threads.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - The words being spell checked.
+    /// * `verbosity` - The value controlling the quantity/closeness of the retuned suggestions.
+    /// * `max_edit_distance` - The maximum edit distance between input and suggested words.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symspell::{SymSpell, AsciiStringStrategy, Verbosity};
+    ///
+    /// let mut symspell: SymSpell<AsciiStringStrategy> = SymSpell::default();
+    /// symspell.load_dictionary("data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+    /// symspell.lookup_batch(&["roket", "whatver", "roket"], Verbosity::Top, 2);
+    /// ```
+    pub fn lookup_batch(
         &self,
-        delete: &str,
-        delete_len: i64,
-        suggestion: &str,
-        suggestion_len: i64,
-    ) -> bool {
-        if delete_len == 0 {
+        inputs: &[&str],
+        verbosity: Verbosity,
+        max_edit_distance: i64,
+    ) -> Vec<Vec<Suggestion>>
+    where
+        T: Sync,
+    {
+        self.batch(inputs, |input| {
+            self.lookup(input, verbosity, max_edit_distance)
+        })
+    }
+
+    /// Find suggested spellings for multiple input sentences at once.
+    ///
+    /// Identical inputs are looked up only once. With the `rayon` feature
+    /// enabled, the lookups are spread across threads.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - The sentences being spell checked.
+    /// * `max_edit_distance` - The maximum edit distance between input and suggested words.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use symspell::{SymSpell, AsciiStringStrategy};
+    ///
+    /// let mut symspell: SymSpell<AsciiStringStrategy> = SymSpell::default();
+    /// symspell.load_dictionary("data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+    /// symspell.lookup_compound_batch(&["whereis th elove", "thequick brown"], 2);
+    /// ```
+    pub fn lookup_compound_batch(
+        &self,
+        inputs: &[&str],
+        edit_distance_max: i64,
+    ) -> Vec<Vec<Suggestion>>
+    where
+        T: Sync,
+    {
+        self.batch(inputs, |input| {
+            self.lookup_compound(input, edit_distance_max)
+        })
+    }
+
+    fn batch<R, F>(&self, inputs: &[&str], f: F) -> Vec<R>
+    where
+        R: Clone + Send,
+        F: Fn(&str) -> R + Sync,
+    {
+        let mut unique_indices: HashMap<&str, usize> = HashMap::new();
+        let mut unique_inputs: Vec<&str> = Vec::new();
+        let positions: Vec<usize> = inputs
+            .iter()
+            .map(|input| {
+                *unique_indices.entry(input).or_insert_with(|| {
+                    unique_inputs.push(input);
+                    unique_inputs.len() - 1
+                })
+            })
+            .collect();
+
+        #[cfg(feature = "rayon")]
+        let results: Vec<R> = unique_inputs.par_iter().map(|input| f(input)).collect();
+        #[cfg(not(feature = "rayon"))]
+        let results: Vec<R> = unique_inputs.iter().map(|input| f(input)).collect();
+
+        positions.into_iter().map(|i| results[i].clone()).collect()
+    }
+
+    fn check_max_edit_distance(&self, max_edit_distance: i64) -> Result<(), SymSpellError> {
+        if max_edit_distance > self.max_dictionary_edit_distance {
+            return Err(SymSpellError::MaxEditDistanceTooLarge {
+                max_edit_distance,
+                max_dictionary_edit_distance: self.max_dictionary_edit_distance,
+            });
+        }
+        Ok(())
+    }
+
+    fn delete_in_suggestion_prefix(&self, delete: &Decoded, suggestion: &Decoded) -> bool {
+        if delete.is_empty() {
             return true;
         }
-        let suggestion_len = if self.prefix_length < suggestion_len {
-            self.prefix_length
-        } else {
-            suggestion_len
-        };
+        let suggestion_len = cmp::min(self.prefix_length as usize, suggestion.len());
         let mut j = 0;
-        for i in 0..delete_len {
-            let del_char = self.string_strategy.at(delete, i as isize).unwrap();
-            while j < suggestion_len
-                && del_char != self.string_strategy.at(suggestion, j as isize).unwrap()
-            {
+        for i in 0..delete.len() {
+            let del_char = delete.at(i);
+            while j < suggestion_len && del_char != suggestion.at(j) {
                 j += 1;
             }
 
             if j == suggestion_len {
                 return false;
             }
+            j += 1;
         }
         true
     }
@@ -770,28 +1760,33 @@ impl<T: StringStrategy> SymSpell<T> {
     where
         K: Clone + AsRef<str> + Into<String>,
     {
+        self.total_word_count = self.total_word_count.saturating_add(cmp::max(count, 0));
+
         if count < self.count_threshold {
             return false;
         }
 
-        let key_clone = key.clone().into().into_boxed_str();
-
-        match self.words.get(key.as_ref()) {
-            Some(i) => {
-                let updated_count = if i64::MAX - i > count {
+        let mut hash = self.get_string_hash(key.as_ref());
+        while let Some(&id) = self.words.get(&hash) {
+            if self.terms[id as usize].as_ref() == key.as_ref() {
+                let i = self.counts[id as usize];
+                self.counts[id as usize] = if i64::MAX - i > count {
                     i + count
                 } else {
                     i64::MAX
                 };
-                self.words.insert(key_clone, updated_count);
                 return false;
             }
-            None => {
-                self.words.insert(key_clone, count);
-            }
+            hash = hash.wrapping_add(1);
         }
 
-        let key_len = self.string_strategy.len(key.as_ref());
+        let key_id =
+            u32::try_from(self.terms.len()).expect("dictionary exceeds u32::MAX distinct words");
+        self.words.insert(hash, key_id);
+        self.terms.push(key.clone().into().into_boxed_str());
+        self.counts.push(count);
+
+        let key_len = self.char_len(key.as_ref());
 
         if key_len as i64 > self.max_length {
             self.max_length = key_len as i64;
@@ -802,10 +1797,7 @@ impl<T: StringStrategy> SymSpell<T> {
         for delete in edits {
             let delete_hash = self.get_string_hash(&delete);
 
-            self.deletes
-                .entry(delete_hash)
-                .and_modify(|e| e.push(key.clone().into().into_boxed_str()))
-                .or_insert_with(|| vec![key.clone().into().into_boxed_str()]);
+            self.deletes.entry(delete_hash).or_default().push(key_id);
         }
 
         true
@@ -814,33 +1806,35 @@ impl<T: StringStrategy> SymSpell<T> {
     fn edits_prefix(&self, key: &str) -> HashSet<String> {
         let mut hash_set = HashSet::new();
 
-        let key_len = self.string_strategy.len(key) as i64;
+        let mut offsets = Vec::new();
+        let key = self.string_strategy.decode(key, &mut offsets);
+        let key_len = key.len() as i64;
 
         if key_len <= self.max_dictionary_edit_distance {
             hash_set.insert("".to_string());
         }
 
-        if key_len > self.prefix_length {
-            let shortened_key = self
-                .string_strategy
-                .slice(key, 0, self.prefix_length as usize);
-            hash_set.insert(shortened_key.clone());
-            self.edits(&shortened_key, 0, &mut hash_set);
+        let key = if key_len > self.prefix_length {
+            key.slice(0, self.prefix_length as usize)
         } else {
-            hash_set.insert(key.to_string());
-            self.edits(key, 0, &mut hash_set);
+            key.as_str()
         };
+        hash_set.insert(key.to_string());
+        self.edits(key, 0, &mut hash_set);
 
         hash_set
     }
 
     fn edits(&self, word: &str, edit_distance: i64, delete_words: &mut HashSet<String>) {
         let edit_distance = edit_distance + 1;
-        let word_len = self.string_strategy.len(word);
+        let mut offsets = Vec::new();
+        let word = self.string_strategy.decode(word, &mut offsets);
 
-        if word_len > 1 {
-            for i in 0..word_len {
-                let delete = self.string_strategy.remove(word, i);
+        if word.len() > 1 {
+            let mut delete = String::new();
+
+            for i in 0..word.len() {
+                word.remove_into(i, &mut delete);
 
                 if !delete_words.contains(&delete) {
                     delete_words.insert(delete.clone());
@@ -856,12 +1850,16 @@ impl<T: StringStrategy> SymSpell<T> {
     fn has_different_suffix(
         &self,
         max_edit_distance: i64,
-        input: &str,
-        input_len: i64,
+        input: &Decoded,
         candidate_len: i64,
-        suggestion: &str,
-        suggestion_len: i64,
+        suggestion: &Decoded,
     ) -> bool {
+        let input_len = input.len() as i64;
+        let suggestion_len = suggestion.len() as i64;
+        fn at<'a>(s: &Decoded<'a>, i: i64) -> Option<&'a str> {
+            usize::try_from(i).ok().and_then(|i| s.at(i))
+        }
+
         // handles the shortcircuit of min_distance
         // assignment when first boolean expression
         // evaluates to false
@@ -873,47 +1871,254 @@ impl<T: StringStrategy> SymSpell<T> {
 
         (self.prefix_length - max_edit_distance == candidate_len)
             && (((min - self.prefix_length) > 1)
-                && (self
-                    .string_strategy
-                    .suffix(input, (input_len + 1 - min) as usize)
-                    != self
-                        .string_strategy
-                        .suffix(suggestion, (suggestion_len + 1 - min) as usize)))
+                && (input.suffix((input_len + 1 - min) as usize)
+                    != suggestion.suffix((suggestion_len + 1 - min) as usize)))
             || ((min > 0)
-                && (self.string_strategy.at(input, (input_len - min) as isize)
-                    != self
-                        .string_strategy
-                        .at(suggestion, (suggestion_len - min) as isize))
-                && ((self
-                    .string_strategy
-                    .at(input, (input_len - min - 1) as isize)
-                    != self
-                        .string_strategy
-                        .at(suggestion, (suggestion_len - min) as isize))
-                    || (self.string_strategy.at(input, (input_len - min) as isize)
-                        != self
-                            .string_strategy
-                            .at(suggestion, (suggestion_len - min - 1) as isize))))
+                && (at(input, input_len - min) != at(suggestion, suggestion_len - min))
+                && ((at(input, input_len - min - 1) != at(suggestion, suggestion_len - min))
+                    || (at(input, input_len - min) != at(suggestion, suggestion_len - min - 1))))
+    }
+
+    /// Sets the confidence of every suggestion to its share of the probability
+    /// of all candidates within `max_edit_distance`.
+    ///
+    /// A candidate is weighted by its unigram probability, times the error
+    /// model probability of the input or `EDIT_PROBABILITY` per edit. An input
+    /// missing from the dictionary is itself a candidate, with the probability
+    /// of an unknown word, so rare or distant suggestions get a low confidence.
+    fn set_confidence(
+        &self,
+        input: &str,
+        verbosity: Verbosity,
+        max_edit_distance: i64,
+        suggestions: &mut [Suggestion],
+    ) {
+        let all;
+        let candidates = if verbosity == Verbosity::All {
+            &*suggestions
+        } else {
+            all = self.lookup_terms(input, Verbosity::All, max_edit_distance);
+            &all
+        };
+
+        let input = self.prepare(input);
+        let mut total: f64 = candidates
+            .iter()
+            .map(|c| self.channel_weight(&input, c))
+            .sum();
+        if !candidates.iter().any(|c| c.distance == 0) {
+            total += self.ngram_probability(&[input.as_str()]);
+        }
+
+        for suggestion in suggestions {
+            suggestion.confidence = Some(self.channel_weight(&input, suggestion) / total);
+        }
+    }
+
+    /// Unnormalized probability that `suggestion` was intended when typing the
+    /// prepared `input`: `P(input | word) * P(word)`, with `P(input | word)` from
+    /// the error model of `Ranking::NoisyChannel`, otherwise `EDIT_PROBABILITY`
+    /// per edit.
+    fn channel_weight(&self, input: &str, suggestion: &Suggestion) -> f64 {
+        let error_probability = match &self.ranking {
+            Ranking::NoisyChannel(model) => {
+                model.probability_with(&self.string_strategy, input, &suggestion.term)
+            }
+            Ranking::Distance => EDIT_PROBABILITY.powi(suggestion.distance as i32),
+        };
+
+        error_probability * suggestion.count as f64 / self.corpus_word_count() as f64
+    }
+
+    /// Log10 probability of `suggestion` between `prev_word` and `next_word`.
+    fn context_score(
+        &self,
+        prev_word: Option<&str>,
+        suggestion: &Suggestion,
+        next_word: Option<&str>,
+    ) -> f64 {
+        let term = suggestion.term.as_str();
+
+        let left = match prev_word.filter(|w| self.word_id(w).is_some()) {
+            Some(prev_word) => self.ngram_probability(&[prev_word, term]),
+            None => suggestion.count as f64 / self.corpus_word_count() as f64,
+        };
+        let right = match next_word.filter(|w| self.word_id(w).is_some()) {
+            Some(next_word) => self.ngram_probability(&[term, next_word]),
+            None => 1.0,
+        };
+
+        left.log10() + right.log10() + suggestion.distance as f64 * EDIT_PROBABILITY.log10()
+    }
+
+    /// Replaces a dictionary word with the edit distance 1 neighbour its context
+    /// favours, if the confidence in it reaches `real_word_threshold`.
+    ///
+    /// The confidence is the neighbour's share of the context probabilities of
+    /// the word and its neighbours. Only neighbours forming a loaded bigram with
+    /// the previous or next word are considered, so words without bigram
+    /// evidence for a neighbour are kept.
+    fn correct_real_word(
+        &self,
+        prev_word: Option<&str>,
+        suggestion: &Suggestion,
+        next_word: Option<&str>,
+    ) -> Suggestion {
+        let threshold = match self.real_word_threshold {
+            Some(threshold) if self.max_dictionary_edit_distance >= 1 => threshold,
+            _ => return suggestion.clone(),
+        };
+        let prev_word = prev_word.filter(|w| self.word_id(w).is_some());
+        let next_word = next_word.filter(|w| self.word_id(w).is_some());
+        if prev_word.is_none() && next_word.is_none() {
+            return suggestion.clone();
+        }
+
+        let has_bigram = |first: &str, second: &str| {
+            self.bigrams
+                .contains_key(format!("{} {}", first, second).as_str())
+        };
+        let candidates: Vec<Suggestion> = self
+            .lookup_terms(&suggestion.term, Verbosity::All, 1)
+            .into_iter()
+            .filter(|candidate| {
+                candidate.term == suggestion.term
+                    || prev_word.is_some_and(|prev_word| has_bigram(prev_word, &candidate.term))
+                    || next_word.is_some_and(|next_word| has_bigram(&candidate.term, next_word))
+            })
+            .collect();
+        let scores: Vec<f64> = candidates
+            .iter()
+            .map(|candidate| self.context_score(prev_word, candidate, next_word))
+            .collect();
+
+        let mut best = 0;
+        for (i, score) in scores.iter().enumerate() {
+            if *score > scores[best] {
+                best = i;
+            }
+        }
+        if candidates.is_empty() || candidates[best].term == suggestion.term {
+            return suggestion.clone();
+        }
+
+        let total: f64 = scores
+            .iter()
+            .map(|score| 10f64.powf(score - scores[best]))
+            .sum();
+
+        if 1.0 / total >= threshold {
+            candidates[best].clone()
+        } else {
+            suggestion.clone()
+        }
+    }
+
+    /// Stupid backoff probability of the last of `words` given the preceding ones.
+    fn ngram_probability(&self, words: &[&str]) -> f64 {
+        let (word, context) = words.split_last().expect("at least one word");
+
+        if context.is_empty() {
+            return match self.word_frequency(word) {
+                Some(count) => count as f64 / self.corpus_word_count() as f64,
+                None => {
+                    10.0 / (self.corpus_word_count() as f64
+                        * 10f64.powf(self.char_len(word) as f64))
+                }
+            };
+        }
+
+        match (self.ngram_count(words), self.ngram_count(context)) {
+            (Some(count), Some(context_count)) if context_count > 0 => {
+                (count as f64 / context_count as f64).min(1.0)
+            }
+            _ => BACKOFF_FACTOR * self.ngram_probability(&words[1..]),
+        }
+    }
+
+    fn ngram_count(&self, words: &[&str]) -> Option<i64> {
+        match words.len() {
+            1 => self.word_frequency(words[0]),
+            2 => self.bigrams.get(words.join(" ").as_str()).copied(),
+            _ => self.ngrams.get(words.join(" ").as_str()).copied(),
+        }
+    }
+
+    /// Prepares an input with the string strategy and applies the case handling.
+    fn prepare(&self, s: &str) -> String {
+        let prepared = self.string_strategy.prepare(s);
+
+        match self.case_handling.apply(&prepared) {
+            Cow::Borrowed(_) => prepared,
+            Cow::Owned(applied) => applied,
+        }
+    }
+
+    /// Prepares a sentence of `lookup_compound`, lowercased under
+    /// `CaseHandling::LowercaseCompound`.
+    fn prepare_sentence(&self, s: &str) -> String {
+        let prepared = self.prepare(s);
+
+        if self.case_handling == CaseHandling::LowercaseCompound {
+            prepared.to_lowercase()
+        } else {
+            prepared
+        }
+    }
+
+    fn add_display_form(&mut self, key: &str, form: &str, count: i64) {
+        match self.display_forms.get_mut(key) {
+            // equal counts are resolved on the form, not on the load order
+            Some(best) if count > best.1 || (count == best.1 && form < &*best.0) => {
+                *best = (form.into(), count)
+            }
+            Some(_) => (),
+            None => {
+                self.display_forms.insert(key.into(), (form.into(), count));
+            }
+        }
+    }
+
+    /// The dictionary casing of a word under `CaseHandling::Insensitive`.
+    fn display_form<'a>(&'a self, word: &'a str) -> &'a str {
+        match self.display_forms.get(word) {
+            Some((form, _)) => form,
+            None => word,
+        }
+    }
+
+    /// Number of characters of `s`, as split by the string strategy.
+    fn char_len(&self, s: &str) -> usize {
+        self.string_strategy.decode(s, &mut Vec::new()).len()
     }
 
     fn get_string_hash(&self, s: &str) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        s.hash(&mut hasher);
-        hasher.finish()
+        self.delete_hasher.hash(s)
     }
 
-    fn parse_words(&self, text: &str) -> Vec<String> {
-        text.to_lowercase()
-            .split_whitespace()
-            .map(|s| s.to_string())
-            .collect()
+    fn word_id(&self, word: &str) -> Option<u32> {
+        let mut hash = self.get_string_hash(word);
+        while let Some(&id) = self.words.get(&hash) {
+            if self.terms[id as usize].as_ref() == word {
+                return Some(id);
+            }
+            hash = hash.wrapping_add(1);
+        }
+        None
+    }
+
+    fn word_frequency(&self, word: &str) -> Option<i64> {
+        self.word_id(word).map(|id| self.counts[id as usize])
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::string_strategy::UnicodeStringStrategy;
+    use crate::error_model::ErrorModel;
+    use crate::string_strategy::{
+        GraphemeStringStrategy, NormalizingStringStrategy, UnicodeStringStrategy,
+    };
 
     #[test]
     fn test_lookup_compound_overflow() {
@@ -1035,6 +2240,32 @@ mod tests {
         assert!(results.iter().any(|s| s.term == "rocket"));
         // all results should be within edit distance 2
         assert!(results.iter().all(|s| s.distance <= 2));
+        // best first: ascending distance, then descending count, then term
+        assert!(results.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_lookup_all_order() {
+        let mut sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+        sym_spell.load_dictionary("./data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+
+        // order of the reference implementation, whose SuggestItem.CompareTo
+        // sorts by distance, then descending count
+        let results = sym_spell.lookup("hous", Verbosity::All, 1);
+        let results: Vec<(&str, i64, i64)> = results
+            .iter()
+            .take(4)
+            .map(|s| (s.term.as_str(), s.distance, s.count))
+            .collect();
+        assert_eq!(
+            vec![
+                ("house", 1, 231_310_420),
+                ("hours", 1, 198_242_904),
+                ("hour", 1, 71_765_763),
+                ("nous", 1, 2_259_869),
+            ],
+            results
+        );
     }
 
     #[test]
@@ -1101,6 +2332,832 @@ mod tests {
         assert!(!result);
     }
 
+    #[test]
+    fn test_lookup_batch() {
+        let mut sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+        sym_spell.load_dictionary("./data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+
+        let results = sym_spell.lookup_batch(&["roket", "xzxzxzxz", "roket"], Verbosity::Top, 2);
+        assert_eq!(3, results.len());
+        assert_eq!("rocket", results[0][0].term);
+        assert!(results[1].is_empty());
+        assert_eq!("rocket", results[2][0].term);
+    }
+
+    #[test]
+    fn test_lookup_compound_batch() {
+        let mut sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+        sym_spell.load_dictionary("./data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+
+        let inputs = ["whereis th elove", "the bigjest playrs", "whereis th elove"];
+        let results = sym_spell.lookup_compound_batch(&inputs, 2);
+        assert_eq!(3, results.len());
+        assert_eq!("whereas the love", results[0][0].term);
+        assert_eq!("the biggest players", results[1][0].term);
+        assert_eq!("whereas the love", results[2][0].term);
+    }
+
+    #[test]
+    fn test_lookup_compound_with_tokenizer() {
+        let mut sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+        sym_spell.load_dictionary_line("well 100", 0, 1, " ");
+        sym_spell.load_dictionary_line("known 100", 0, 1, " ");
+
+        let hyphens = |text: &str| -> Vec<String> {
+            text.split(|c: char| c.is_whitespace() || c == '-')
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+                .collect()
+        };
+        let results = sym_spell.lookup_compound_with_tokenizer("well-knwn", 2, &hyphens);
+        assert_eq!("well known", results[0].term);
+
+        assert_eq!(
+            sym_spell.lookup_compound("well knwn", 2),
+            sym_spell.lookup_compound_with_tokenizer("well knwn", 2, &WhitespaceTokenizer)
+        );
+        assert!(
+            sym_spell
+                .try_lookup_compound_with_tokenizer("well-knwn", 3, &hyphens)
+                .is_err()
+        );
+    }
+
+    fn case_handling_symspell(case_handling: CaseHandling) -> SymSpell<UnicodeStringStrategy> {
+        let mut sym_spell: SymSpell<UnicodeStringStrategy> = SymSpellBuilder::default()
+            .case_handling(case_handling)
+            .build()
+            .unwrap();
+        sym_spell.load_dictionary_line("iPhone 100", 0, 1, " ");
+        sym_spell.load_dictionary_line("iphone 10", 0, 1, " ");
+        sym_spell.load_dictionary_line("US 50", 0, 1, " ");
+        sym_spell.load_dictionary_line("new 200", 0, 1, " ");
+        sym_spell
+    }
+
+    #[test]
+    fn test_case_handling_default() {
+        let mut sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+        assert_eq!(CaseHandling::LowercaseCompound, sym_spell.case_handling());
+        for line in ["iPhone 10", "iphone 5", "new 200"] {
+            sym_spell.load_dictionary_line(line, 0, 1, " ");
+        }
+
+        // dictionary words and lookup inputs are kept as is
+        assert_eq!(3, sym_spell.word_count());
+        assert_eq!(
+            vec![Suggestion::new("iPhone", 0, 10)],
+            sym_spell.lookup("iPhone", Verbosity::Top, 2)
+        );
+        assert_eq!(
+            "iPhone",
+            sym_spell.word_segmentation("iPhone", 2).segmented_string
+        );
+        // while lookup_compound lowercases its input
+        assert_eq!(
+            "new iphone",
+            sym_spell.lookup_compound("New iPhone", 2)[0].term
+        );
+    }
+
+    #[test]
+    fn test_case_handling_lowercase() {
+        let sym_spell = case_handling_symspell(CaseHandling::Lowercase);
+        assert_eq!(CaseHandling::Lowercase, sym_spell.case_handling());
+
+        let results = sym_spell.lookup("IPHNOE", Verbosity::Top, 2);
+        assert_eq!("iphone", results[0].term);
+        assert_eq!(110, results[0].count);
+        assert_eq!(
+            "new iphone",
+            sym_spell.lookup_compound("New iPhnoe", 2)[0].term
+        );
+        assert_eq!(
+            "new iphone",
+            sym_spell.word_segmentation("NewiPhone", 2).segmented_string
+        );
+    }
+
+    #[test]
+    fn test_case_handling_sensitive() {
+        let sym_spell = case_handling_symspell(CaseHandling::Sensitive);
+
+        let results = sym_spell.lookup("US", Verbosity::Top, 2);
+        assert_eq!("US", results[0].term);
+        assert_eq!(0, results[0].distance);
+        assert_eq!(
+            "new iPhone",
+            sym_spell.lookup_compound("new iPhnoe", 2)[0].term
+        );
+        assert_eq!(
+            "new iPhone",
+            sym_spell.word_segmentation("newiPhone", 2).segmented_string
+        );
+    }
+
+    #[test]
+    fn test_case_handling_insensitive() {
+        let sym_spell = case_handling_symspell(CaseHandling::Insensitive);
+
+        let results = sym_spell.lookup("IPHNOE", Verbosity::Top, 2);
+        assert_eq!("iPhone", results[0].term);
+        assert_eq!(110, results[0].count);
+        let results = sym_spell.lookup("us", Verbosity::Top, 2);
+        assert_eq!("US", results[0].term);
+        assert_eq!(0, results[0].distance);
+        assert_eq!(
+            "new iPhone US",
+            sym_spell.lookup_compound("NEW iphnoe us", 2)[0].term
+        );
+        assert_eq!(
+            "new iPhone",
+            sym_spell.word_segmentation("newiphone", 2).segmented_string
+        );
+    }
+
+    #[test]
+    fn test_lookup_in_context() {
+        let mut sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+        sym_spell.load_dictionary_line("their 100", 0, 1, " ");
+        sym_spell.load_dictionary_line("there 300", 0, 1, " ");
+        sym_spell.load_dictionary_line("house 100", 0, 1, " ");
+        sym_spell.load_dictionary_line("over 100", 0, 1, " ");
+        sym_spell.load_bigram_dictionary_line("their house 50", 0, 2, " ");
+        sym_spell.load_bigram_dictionary_line("over there 30", 0, 2, " ");
+
+        let results = sym_spell.lookup_in_context(None, "ther", None, 2);
+        assert_eq!("there", results[0].suggestion.term);
+        assert_eq!(1, results[0].suggestion.distance);
+        assert!(results[0].score > results[1].score);
+
+        let results = sym_spell.lookup_in_context(None, "ther", Some("house"), 2);
+        assert_eq!("their", results[0].suggestion.term);
+        assert_eq!(100, results[0].suggestion.count);
+
+        let results = sym_spell.lookup_in_context(Some("over"), "ther", None, 2);
+        assert_eq!("there", results[0].suggestion.term);
+
+        // unknown neighbours are ignored
+        let results = sym_spell.lookup_in_context(Some("xyzzy"), "ther", Some("qux"), 2);
+        assert_eq!("there", results[0].suggestion.term);
+        assert!(results[0].score.is_finite());
+
+        assert!(
+            sym_spell
+                .try_lookup_in_context(None, "ther", None, 3)
+                .is_err()
+        );
+    }
+
+    fn real_word_symspell(threshold: Option<f64>) -> SymSpell<UnicodeStringStrategy> {
+        let mut builder = SymSpellBuilder::default();
+        if let Some(threshold) = threshold {
+            builder.real_word_threshold(threshold);
+        }
+        let mut sym_spell: SymSpell<UnicodeStringStrategy> = builder.build().unwrap();
+        for line in [
+            "i 500", "want 100", "to 1000", "by 300", "buy 100", "a 1000", "car 50",
+        ] {
+            sym_spell.load_dictionary_line(line, 0, 1, " ");
+        }
+        for line in ["to buy 80", "to by 1", "buy a 50", "by a 5"] {
+            sym_spell.load_bigram_dictionary_line(line, 0, 2, " ");
+        }
+        sym_spell
+    }
+
+    #[test]
+    fn test_lookup_compound_real_word_errors() {
+        let sentence = "i want to by a car";
+
+        let sym_spell = real_word_symspell(None);
+        assert_eq!(None, sym_spell.real_word_threshold());
+        assert_eq!(sentence, sym_spell.lookup_compound(sentence, 2)[0].term);
+
+        let sym_spell = real_word_symspell(Some(0.9));
+        assert_eq!(Some(0.9), sym_spell.real_word_threshold());
+        assert_eq!(
+            "i want to buy a car",
+            sym_spell.lookup_compound(sentence, 2)[0].term
+        );
+        // without known neighbours there is no context to go by
+        assert_eq!("by", sym_spell.lookup_compound("by", 2)[0].term);
+
+        // nor without bigrams, however rare the word is
+        let mut rare_word: SymSpell<UnicodeStringStrategy> = SymSpellBuilder::default()
+            .real_word_threshold(0.9)
+            .build()
+            .unwrap();
+        for line in ["a 1000", "quaint 10", "old 200", "inn 2", "in 5000"] {
+            rare_word.load_dictionary_line(line, 0, 1, " ");
+        }
+        assert_eq!(
+            "a quaint old inn",
+            rare_word.lookup_compound("a quaint old inn", 2)[0].term
+        );
+        rare_word.load_bigram_dictionary_line("old in 1", 0, 2, " ");
+        assert_eq!(
+            "a quaint old in",
+            rare_word.lookup_compound("a quaint old inn", 2)[0].term
+        );
+
+        let sym_spell = real_word_symspell(Some(1.0));
+        assert_eq!(sentence, sym_spell.lookup_compound(sentence, 2)[0].term);
+
+        assert!(
+            SymSpellBuilder::<UnicodeStringStrategy>::default()
+                .real_word_threshold(1.5)
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_sentence_score() {
+        let mut sym_spell: SymSpell<UnicodeStringStrategy> = SymSpellBuilder::default()
+            .corpus_word_count(100)
+            .build()
+            .unwrap();
+        for line in ["a 10", "b 10", "c 10"] {
+            sym_spell.load_dictionary_line(line, 0, 1, " ");
+        }
+        sym_spell.load_bigram_dictionary_line("a b 5", 0, 2, " ");
+        assert!(sym_spell.load_ngram_dictionary_line("b c\t2", 2, 0, 1, "\t"));
+        assert!(!sym_spell.load_ngram_dictionary_line("b\t1", 2, 0, 1, "\t"));
+        assert!(sym_spell.load_ngram_dictionary_line("a b c 2", 3, 0, 3, " "));
+        assert!(!sym_spell.load_ngram_dictionary_line("a b c x", 3, 0, 3, " "));
+        assert!(!sym_spell.load_ngram_dictionary_line("a 2", 1, 0, 1, " "));
+
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        // P(a) * P(b | a) * P(c | a b)
+        assert!(close(
+            sym_spell.sentence_score("a b c"),
+            (0.1f64 * 0.5 * 0.4).log10()
+        ));
+        // P(b) * 0.4 * P(a)
+        assert!(close(
+            sym_spell.sentence_score("b a"),
+            (0.1f64 * 0.04).log10()
+        ));
+        // P(c) * 0.4 * P(a) * 0.4 * P(b | a)
+        assert!(close(
+            sym_spell.sentence_score("c a b"),
+            (0.1f64 * 0.04 * 0.2).log10()
+        ));
+        assert!(close(sym_spell.sentence_score(""), 0.0));
+        assert!(sym_spell.sentence_score("a b x") < sym_spell.sentence_score("a b c"));
+    }
+
+    #[test]
+    fn test_lookup_compound_beam() {
+        let sym_spell = real_word_symspell(None);
+        let sentence = "i want to bu a car";
+
+        // greedy lookup picks the more frequent "by"
+        assert_eq!(
+            "i want to by a car",
+            sym_spell.lookup_compound(sentence, 2)[0].term
+        );
+
+        let results = sym_spell.lookup_compound_beam(sentence, 2, 4, 5);
+        assert_eq!(2, results.len());
+        assert_eq!("i want to buy a car", results[0].term);
+        assert_eq!(1, results[0].distance);
+        assert_eq!("i want to by a car", results[1].term);
+        assert!(results[0].score > results[1].score);
+
+        let results = sym_spell.lookup_compound_beam("i want xyzzy", 2, 4, 1);
+        assert_eq!(1, results.len());
+        assert_eq!("i want xyzzy", results[0].term);
+
+        assert!(sym_spell.lookup_compound_beam(sentence, 2, 4, 0).is_empty());
+        assert!(
+            sym_spell
+                .try_lookup_compound_beam(sentence, 3, 4, 1)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_lookup_compound_top_k() {
+        let sym_spell = real_word_symspell(None);
+
+        let results = sym_spell.lookup_compound_top_k("i want to bu a car", 2, 5);
+        assert_eq!(2, results.len());
+        assert_eq!("i want to buy a car", results[0].term);
+        assert_eq!("i want to by a car", results[1].term);
+        assert!(results[0].score > results[1].score);
+        let expected = sym_spell.sentence_score("i want to buy a car") + EDIT_PROBABILITY.log10();
+        assert!((results[0].score - expected).abs() < 1e-9);
+
+        // alternatives include the splits of lookup_compound
+        let greedy = sym_spell.lookup_compound("i wantto buy", 2);
+        let results = sym_spell.lookup_compound_top_k("i wantto buy", 2, 5);
+        assert!(results.iter().any(|r| r.term == greedy[0].term));
+
+        assert_eq!(
+            1,
+            sym_spell
+                .lookup_compound_top_k("i want to bu a car", 2, 1)
+                .len()
+        );
+        assert!(
+            sym_spell
+                .lookup_compound_top_k("i want to bu a car", 2, 0)
+                .is_empty()
+        );
+        assert!(
+            sym_spell
+                .try_lookup_compound_top_k("i want to bu a car", 3, 1)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_lookup_confidence() {
+        let mut sym_spell: SymSpell<UnicodeStringStrategy> = SymSpellBuilder::default()
+            .compute_confidence(true)
+            .build()
+            .unwrap();
+        assert!(sym_spell.compute_confidence());
+        for line in ["their 100", "there 300", "rocket 1000000", "rocker 10"] {
+            sym_spell.load_dictionary_line(line, 0, 1, " ");
+        }
+
+        // a frequent word far ahead of the alternatives
+        let results = sym_spell.lookup("roket", Verbosity::Top, 2);
+        assert_eq!("rocket", results[0].term);
+        assert!(results[0].confidence.unwrap() > 0.99);
+
+        // two equally close candidates split the probability
+        let results = sym_spell.lookup("ther", Verbosity::All, 2);
+        let confidence = |term: &str| {
+            results
+                .iter()
+                .find(|r| r.term == term)
+                .and_then(|r| r.confidence)
+                .unwrap()
+        };
+        assert!((confidence("there") / confidence("their") - 3.0).abs() < 1e-9);
+        assert!(confidence("there") + confidence("their") < 1.0);
+
+        let top = sym_spell.lookup("ther", Verbosity::Top, 2);
+        assert_eq!("there", top[0].term);
+        assert_eq!(confidence("there"), top[0].confidence.unwrap());
+
+        let results = sym_spell.lookup("rocket", Verbosity::Top, 2);
+        assert!(results[0].confidence.unwrap() > 0.99);
+
+        let sym_spell = real_word_symspell(None);
+        assert_eq!(
+            None,
+            sym_spell.lookup("bu", Verbosity::Top, 2)[0].confidence
+        );
+    }
+
+    #[test]
+    fn test_lookup_noisy_channel() {
+        let mut model = ErrorModel::new();
+        for _ in 0..5 {
+            model.train("bet", "but");
+            model.train("hem", "hum");
+        }
+
+        let mut sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+        let mut noisy_channel: SymSpell<UnicodeStringStrategy> = SymSpellBuilder::default()
+            .ranking(Ranking::NoisyChannel(Box::new(model.clone())))
+            .compute_confidence(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            &Ranking::NoisyChannel(Box::new(model)),
+            noisy_channel.ranking()
+        );
+        for line in ["cat 100", "cut 50", "cute 60"] {
+            sym_spell.load_dictionary_line(line, 0, 1, " ");
+            noisy_channel.load_dictionary_line(line, 0, 1, " ");
+        }
+
+        assert_eq!("cat", sym_spell.lookup("cet", Verbosity::Top, 2)[0].term);
+
+        let results = noisy_channel.lookup("cet", Verbosity::Top, 2);
+        assert_eq!(1, results.len());
+        assert_eq!("cut", results[0].term);
+        assert!(results[0].confidence.unwrap() > 0.5);
+
+        let results = noisy_channel.lookup("cet", Verbosity::Closest, 2);
+        let terms: Vec<&str> = results.iter().map(|r| r.term.as_str()).collect();
+        assert_eq!(vec!["cut", "cat"], terms);
+
+        let results = noisy_channel.lookup("cet", Verbosity::All, 2);
+        assert_eq!(3, results.len());
+        assert_eq!("cut", results[0].term);
+    }
+
+    #[test]
+    fn test_deterministic_ties() {
+        let lines = ["bat 10", "cat 10", "hat 10", "at 5", "the 10"];
+        let build = |lines: &[&str], delete_hasher| {
+            let mut sym_spell: SymSpell<UnicodeStringStrategy> = SymSpellBuilder::default()
+                .delete_hasher(delete_hasher)
+                .build()
+                .unwrap();
+            for line in lines {
+                sym_spell.load_dictionary_line(line, 0, 1, " ");
+            }
+            sym_spell
+        };
+        let reversed: Vec<&str> = lines.iter().rev().copied().collect();
+        let instances = [
+            build(&lines, DeleteHasher::default()),
+            build(&reversed, DeleteHasher::default()),
+            build(&reversed, DeleteHasher::Fnv1a { seed: 3 }),
+        ];
+
+        for sym_spell in &instances {
+            assert_eq!(
+                vec![Suggestion::new("bat", 1, 10)],
+                sym_spell.lookup("xat", Verbosity::Top, 2)
+            );
+            let terms: Vec<String> = sym_spell
+                .lookup("xat", Verbosity::Closest, 2)
+                .into_iter()
+                .map(|s| s.term)
+                .collect();
+            assert_eq!(vec!["bat", "cat", "hat", "at"], terms);
+            assert_eq!(
+                instances[0].lookup("xat", Verbosity::All, 2),
+                sym_spell.lookup("xat", Verbosity::All, 2)
+            );
+            assert_eq!("the at", sym_spell.lookup_compound("thexat", 2)[0].term);
+            assert_eq!(
+                instances[0].word_segmentation("thexat", 2),
+                sym_spell.word_segmentation("thexat", 2)
+            );
+        }
+
+        // casings with equal counts
+        for lines in [["iPhone 100", "IPhone 100"], ["IPhone 100", "iPhone 100"]] {
+            let mut sym_spell: SymSpell<UnicodeStringStrategy> = SymSpellBuilder::default()
+                .case_handling(CaseHandling::Insensitive)
+                .build()
+                .unwrap();
+            for line in lines {
+                sym_spell.load_dictionary_line(line, 0, 1, " ");
+            }
+            assert_eq!(
+                "IPhone",
+                sym_spell.lookup("iphon", Verbosity::Top, 2)[0].term
+            );
+        }
+    }
+
+    #[test]
+    fn test_deterministic_results() {
+        let inputs = [
+            "whereis th elove hehad dated forImuch of thepast who couqdn'tread in sixtgrade",
+            "thequickbrownfoxjumpsoverthelazydog",
+            "itwasabrightcolddayinapril",
+        ];
+        let results = |delete_hasher| {
+            let mut sym_spell: SymSpell<UnicodeStringStrategy> = SymSpellBuilder::default()
+                .delete_hasher(delete_hasher)
+                .build()
+                .unwrap();
+            sym_spell.load_dictionary("./data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+            inputs
+                .iter()
+                .map(|input| {
+                    (
+                        sym_spell.lookup(input, Verbosity::All, 2),
+                        sym_spell.lookup_compound(input, 2),
+                        sym_spell.word_segmentation(input, 2),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let expected = results(DeleteHasher::default());
+        assert_eq!(expected, results(DeleteHasher::default()));
+        assert_eq!(expected, results(DeleteHasher::Fnv1a { seed: 1 }));
+    }
+
+    #[test]
+    fn test_lookup_compound_segmentation() {
+        let build = |length: Option<usize>| {
+            let mut builder = SymSpellBuilder::default();
+            if let Some(length) = length {
+                builder.compound_segmentation_length(length);
+            }
+            let mut sym_spell: SymSpell<UnicodeStringStrategy> = builder.build().unwrap();
+            sym_spell.load_dictionary("./data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+            sym_spell
+        };
+
+        let two_way = build(None);
+        assert_eq!(None, two_way.compound_segmentation_length());
+        assert_eq!(
+            "cheque brown jumps",
+            two_way.lookup_compound("thequickbrown jumps", 2)[0].term
+        );
+
+        let sym_spell = build(Some(10));
+        assert_eq!(Some(10), sym_spell.compound_segmentation_length());
+        assert_eq!(
+            "the quick brown jumps",
+            sym_spell.lookup_compound("thequickbrown jumps", 2)[0].term
+        );
+        assert_eq!(
+            "the quick brown fox jumps",
+            sym_spell.lookup_compound("thequikbrownfox jumps", 2)[0].term
+        );
+        // shorter terms are still split in two at most
+        assert_eq!(
+            two_way.lookup_compound("whereis th elove", 2),
+            sym_spell.lookup_compound("whereis th elove", 2)
+        );
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        let mut sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+        assert_eq!(0, sym_spell.memory_usage().total());
+
+        sym_spell.load_dictionary_line("rocket 10", 0, 1, " ");
+        let before = sym_spell.memory_usage();
+
+        // a new word adds its characters to the terms
+        sym_spell.load_dictionary_line("pocket 5", 0, 1, " ");
+        let usage = sym_spell.memory_usage();
+        assert_eq!(before.terms + "pocket".len(), usage.terms);
+        assert!(usage.deletes > before.deletes);
+
+        // an existing word only has its count updated
+        sym_spell.load_dictionary_line("rocket 3", 0, 1, " ");
+        assert_eq!(usage, sym_spell.memory_usage());
+        assert_eq!(Some(13), sym_spell.word_frequency("rocket"));
+        assert_eq!(2, sym_spell.word_count());
+
+        assert_eq!(
+            usage.total(),
+            usage.words + usage.terms + usage.deletes + usage.bigrams
+        );
+    }
+
+    #[test]
+    fn test_memory_usage_deletes_hold_ids() {
+        // both words share their prefix and so their deletes, but only the
+        // terms grow with the length of the word
+        let usage = |word: &str| {
+            let mut sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+            sym_spell.load_dictionary_line(&format!("{} 10", word), 0, 1, " ");
+            sym_spell.memory_usage()
+        };
+        let short = usage("abcdefg");
+        let long = usage(&format!("abcdefg{}", "h".repeat(100)));
+
+        assert_eq!(short.deletes, long.deletes);
+        assert_eq!(short.terms + 100, long.terms);
+    }
+
+    #[test]
+    fn test_word_hash_collision() {
+        let mut sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+        sym_spell.load_dictionary_line("rocket 10", 0, 1, " ");
+        // pretend "pocket" hashes like "rocket"
+        let hash = sym_spell.get_string_hash("pocket");
+        sym_spell.words.insert(hash, 0);
+        sym_spell.load_dictionary_line("pocket 5", 0, 1, " ");
+        sym_spell.load_dictionary_line("pocket 2", 0, 1, " ");
+
+        assert_eq!(2, sym_spell.word_count());
+        assert_eq!(Some(10), sym_spell.word_frequency("rocket"));
+        assert_eq!(Some(7), sym_spell.word_frequency("pocket"));
+        assert_eq!(None, sym_spell.word_frequency("socket"));
+    }
+
+    #[test]
+    fn test_lookup_with_delete_hashers() {
+        for delete_hasher in [
+            DeleteHasher::XxHash64 { seed: 42 },
+            DeleteHasher::Fnv1a { seed: 7 },
+            DeleteHasher::Std,
+        ] {
+            let mut sym_spell: SymSpell<UnicodeStringStrategy> = SymSpellBuilder::default()
+                .delete_hasher(delete_hasher)
+                .build()
+                .unwrap();
+            sym_spell.load_dictionary("./data/frequency_dictionary_en_82_765.txt", 0, 1, " ");
+
+            let results = sym_spell.lookup("roket", Verbosity::Top, 2);
+            assert_eq!(1, results.len());
+            assert_eq!("rocket", results[0].term);
+        }
+    }
+
+    #[test]
+    fn test_delete_in_suggestion_prefix() {
+        let sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+
+        let strategy = sym_spell.string_strategy();
+        let (mut a, mut b) = (Vec::new(), Vec::new());
+        let mut in_prefix = |delete: &str, suggestion: &str| {
+            sym_spell.delete_in_suggestion_prefix(
+                &strategy.decode(delete, &mut a),
+                &strategy.decode(suggestion, &mut b),
+            )
+        };
+
+        assert!(in_prefix("", "rocket"));
+        assert!(in_prefix("rkt", "rocket"));
+        // a colliding hash can bring in words the delete doesn't originate from
+        assert!(!in_prefix("okr", "rocket"));
+        assert!(!in_prefix("cc", "rocket"));
+    }
+
+    #[test]
+    fn test_try_lookup_max_edit_distance_too_large() {
+        let sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+        let error = SymSpellError::MaxEditDistanceTooLarge {
+            max_edit_distance: 3,
+            max_dictionary_edit_distance: 2,
+        };
+
+        assert_eq!(
+            Err(error.clone()),
+            sym_spell.try_lookup("roket", Verbosity::Top, 3)
+        );
+        assert_eq!(
+            Err(error.clone()),
+            sym_spell.try_lookup_compound("whereis th elove", 3)
+        );
+        assert_eq!(
+            error,
+            sym_spell.try_word_segmentation("itwas", 3).unwrap_err()
+        );
+        assert!(sym_spell.try_lookup("roket", Verbosity::Top, 2).is_ok());
+        assert_eq!(
+            Ok(Composition::empty()),
+            sym_spell.try_word_segmentation("", 2)
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "max_edit_distance (3) is bigger than max_dictionary_edit_distance (2)"
+    )]
+    fn test_lookup_max_edit_distance_too_large() {
+        let sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+        sym_spell.lookup("roket", Verbosity::Top, 3);
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        let build = |builder: &mut SymSpellBuilder<UnicodeStringStrategy>| {
+            builder.build().map(|_| ()).map_err(|e| e.to_string())
+        };
+
+        assert!(build(&mut SymSpellBuilder::default()).is_ok());
+        assert!(
+            build(
+                SymSpellBuilder::default()
+                    .max_dictionary_edit_distance(0)
+                    .prefix_length(1)
+                    .count_threshold(0)
+                    .corpus_word_count(1)
+            )
+            .is_ok()
+        );
+
+        let error = build(SymSpellBuilder::default().max_dictionary_edit_distance(-1));
+        assert!(
+            error
+                .unwrap_err()
+                .contains("max_dictionary_edit_distance (-1)")
+        );
+
+        let error = build(SymSpellBuilder::default().prefix_length(2));
+        assert!(error.unwrap_err().contains("prefix_length (2)"));
+
+        let error = build(
+            SymSpellBuilder::default()
+                .max_dictionary_edit_distance(3)
+                .prefix_length(3),
+        );
+        assert!(error.unwrap_err().contains("prefix_length (3)"));
+
+        let error = build(SymSpellBuilder::default().prefix_length(0));
+        assert!(error.unwrap_err().contains("prefix_length (0)"));
+
+        let error = build(SymSpellBuilder::default().count_threshold(-1));
+        assert!(error.unwrap_err().contains("count_threshold (-1)"));
+
+        let error = build(SymSpellBuilder::default().corpus_word_count(0));
+        assert!(error.unwrap_err().contains("corpus_word_count (0)"));
+    }
+
+    #[test]
+    fn test_corpus_word_count() {
+        let mut sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+        sym_spell.load_dictionary_line("rocket 10", 0, 1, " ");
+        assert_eq!(1_024_908_267_229, sym_spell.corpus_word_count());
+
+        let mut sym_spell: SymSpell<UnicodeStringStrategy> = SymSpellBuilder::default()
+            .corpus_word_count(1000)
+            .build()
+            .unwrap();
+        sym_spell.load_dictionary_line("rocket 10", 0, 1, " ");
+        assert_eq!(1000, sym_spell.corpus_word_count());
+    }
+
+    #[test]
+    fn test_derive_corpus_word_count() {
+        let mut sym_spell: SymSpell<UnicodeStringStrategy> = SymSpellBuilder::default()
+            .count_threshold(5)
+            .corpus_word_count(1000)
+            .derive_corpus_word_count(true)
+            .build()
+            .unwrap();
+        assert_eq!(1000, sym_spell.corpus_word_count());
+
+        sym_spell.load_dictionary_line("rocket 10", 0, 1, " ");
+        sym_spell.load_dictionary_line("pocket 20", 0, 1, " ");
+        sym_spell.load_dictionary_line("rocket 5", 0, 1, " ");
+        // below count_threshold, but still part of the corpus
+        sym_spell.load_dictionary_line("socket 2", 0, 1, " ");
+        assert_eq!(37, sym_spell.corpus_word_count());
+    }
+
+    #[test]
+    fn test_lookup_graphemes() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let entry = format!("hi{} 10", family);
+
+        let mut sym_spell = SymSpell::<UnicodeStringStrategy>::default();
+        sym_spell.load_dictionary_line(&entry, 0, 1, " ");
+        assert!(
+            sym_spell
+                .lookup("hi\u{1F468}", Verbosity::Top, 2)
+                .is_empty()
+        );
+
+        let mut sym_spell = SymSpell::<GraphemeStringStrategy>::default();
+        sym_spell.load_dictionary_line(&entry, 0, 1, " ");
+        let results = sym_spell.lookup("hi\u{1F468}", Verbosity::Top, 2);
+        assert_eq!(1, results.len());
+        assert_eq!(format!("hi{}", family), results[0].term);
+        assert_eq!(1, results[0].distance);
+    }
+
+    #[test]
+    fn test_lookup_normalized() {
+        let mut sym_spell = SymSpell::<NormalizingStringStrategy>::default();
+        sym_spell.load_dictionary_line("caf\u{e9} 10", 0, 1, " ");
+        sym_spell.load_dictionary_line("Stra\u{df}e 10", 0, 1, " ");
+
+        let results = sym_spell.lookup("CAFE\u{301}", Verbosity::Top, 2);
+        assert_eq!(1, results.len());
+        assert_eq!("caf\u{e9}", results[0].term);
+        assert_eq!(0, results[0].distance);
+
+        let results = sym_spell.lookup("STRASSE", Verbosity::Top, 2);
+        assert_eq!(1, results.len());
+        assert_eq!("strasse", results[0].term);
+        assert_eq!(0, results[0].distance);
+    }
+
+    #[derive(Clone, Default, PartialEq, Debug)]
+    struct StripStrategy {
+        strip: Vec<char>,
+    }
+
+    impl StringStrategy for StripStrategy {
+        fn prepare(&self, s: &str) -> String {
+            s.chars().filter(|c| !self.strip.contains(c)).collect()
+        }
+    }
+
+    #[test]
+    fn test_configured_string_strategy() {
+        let strategy = StripStrategy {
+            strip: vec!['-', '\''],
+        };
+        let mut sym_spell: SymSpell<StripStrategy> = SymSpellBuilder::default()
+            .string_strategy(strategy.clone())
+            .build()
+            .unwrap();
+        assert_eq!(&strategy, sym_spell.string_strategy());
+
+        sym_spell.load_dictionary_line("e-mail 10", 0, 1, " ");
+        let results = sym_spell.lookup("emai'l", Verbosity::Top, 2);
+        assert_eq!(1, results.len());
+        assert_eq!("email", results[0].term);
+        assert_eq!(0, results[0].distance);
+
+        let sym_spell = SymSpell::<StripStrategy>::default();
+        assert!(sym_spell.string_strategy().strip.is_empty());
+    }
+
     #[test]
     fn test_word_segmentation() {
         let edit_distance_max = 2;
@@ -0,0 +1,49 @@
+/// Splits an input sentence into the terms corrected by `lookup_compound`.
+///
+/// The input has already been passed through the `StringStrategy` `prepare`
+/// step and the `CaseHandling` of the `SymSpell` instance. Corrected terms
+/// are joined with single spaces in the result, so terms must not be empty.
+pub trait Tokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String>;
+}
+
+/// Splits the text on whitespace. Used by `lookup_compound`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WhitespaceTokenizer;
+
+impl Tokenizer for WhitespaceTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        text.split_whitespace().map(|s| s.to_string()).collect()
+    }
+}
+
+impl<F> Tokenizer for F
+where
+    F: Fn(&str) -> Vec<String>,
+{
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        self(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_tokenizer() {
+        assert_eq!(
+            WhitespaceTokenizer.tokenize(" Where\tis  THE\nlove "),
+            vec!["Where", "is", "THE", "love"]
+        );
+        assert!(WhitespaceTokenizer.tokenize(" \t").is_empty());
+    }
+
+    #[test]
+    fn closure_tokenizer() {
+        let tokenizer =
+            |text: &str| -> Vec<String> { text.split(',').map(|s| s.to_string()).collect() };
+        assert_eq!(tokenizer.tokenize("a,b"), vec!["a", "b"]);
+    }
+}
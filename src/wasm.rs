@@ -96,7 +96,10 @@ impl JSSymSpell {
         input: &str,
         edit_distance: i32,
     ) -> Result<Vec<JsValue>, JsValue> {
-        let res = self.symspell.lookup_compound(input, edit_distance as i64);
+        let res = self
+            .symspell
+            .try_lookup_compound(input, edit_distance as i64)
+            .map_err(|e| JsValue::from(e.to_string()))?;
         Ok(res
             .into_iter()
             .map(|sugg| {
@@ -125,7 +128,8 @@ impl JSSymSpell {
 
         let res = self
             .symspell
-            .lookup(&input, sym_verbosity, max_edit_distance as i64);
+            .try_lookup(&input, sym_verbosity, max_edit_distance as i64)
+            .map_err(|e| JsValue::from(e.to_string()))?;
 
         Ok(res
             .into_iter()
@@ -147,7 +151,8 @@ impl JSSymSpell {
     ) -> Result<JsValue, JsValue> {
         let seg = self
             .symspell
-            .word_segmentation(input, max_edit_distance as i64);
+            .try_word_segmentation(input, max_edit_distance as i64)
+            .map_err(|e| JsValue::from(e.to_string()))?;
         let res = JSComposition {
             segmented_string: seg.segmented_string,
             distance_sum: seg.distance_sum as i32,
@@ -210,5 +215,8 @@ mod tests {
         )
         .unwrap();
         assert_eq!(result.segmented_string, expected);
+        assert!(speller.lookup("wher", 0, 3).is_err());
+        assert!(speller.lookup_compound(sentence, 3).is_err());
+        assert!(speller.word_segmentation(sentence, 3).is_err());
     }
 }